@@ -0,0 +1,58 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use papr::mailbox::{
+    message::{
+        header::{Person, Subject},
+        Message,
+    },
+    Mailbox,
+};
+
+const SINGLE_PATCH: &str = include_str!("../src/mailbox/samples/single_patch.mbx");
+const MULTI_PATCHES: &str = include_str!("../src/mailbox/samples/multi_patches.mbx");
+
+/// Number of times the sample mailbox is repeated to simulate a list archive
+const ARCHIVE_COPIES: usize = 1000;
+
+fn headers(c: &mut Criterion) {
+    let mut group = c.benchmark_group("headers");
+
+    group.bench_function("person", |b| {
+        b.iter(|| Person::try_from(black_box("John Doe <john.doe@email.com>")))
+    });
+    group.bench_function("person_at", |b| {
+        b.iter(|| Person::try_from(black_box("John Doe <\"john.doe at email.com\">")))
+    });
+    group.bench_function("subject_patch", |b| {
+        b.iter(|| Subject::try_from(black_box("[PATCH v1 1/10] patch-tree: foo message")))
+    });
+    group.bench_function("subject_simple", |b| {
+        b.iter(|| Subject::try_from(black_box("Some simple subject")))
+    });
+
+    group.finish();
+}
+
+fn mailboxes(c: &mut Criterion) {
+    let archive = MULTI_PATCHES.repeat(ARCHIVE_COPIES);
+    let mut group = c.benchmark_group("mailbox");
+
+    group.throughput(Throughput::Bytes(SINGLE_PATCH.len() as u64));
+    group.bench_function("single_patch", |b| {
+        b.iter(|| Message::try_from(black_box(SINGLE_PATCH)))
+    });
+
+    group.throughput(Throughput::Bytes(MULTI_PATCHES.len() as u64));
+    group.bench_function("multi_patches", |b| {
+        b.iter(|| Mailbox::try_from(black_box(MULTI_PATCHES)))
+    });
+
+    group.throughput(Throughput::Bytes(archive.len() as u64));
+    group.bench_function("archive", |b| {
+        b.iter(|| Mailbox::try_from(black_box(archive.as_str())))
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, headers, mailboxes);
+criterion_main!(benches);
@@ -0,0 +1,381 @@
+use std::{
+    collections::HashSet,
+    fmt::Write as _,
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+
+use color_eyre::eyre::{Context, Result};
+
+use crate::{
+    mailbox::{
+        message::Message,
+        thread::{Series, Threads},
+    },
+    render::{
+        self,
+        html::{self, escape, slug},
+        theme::Theme,
+        Backend, Options, Span,
+    },
+};
+
+/// A static website for a list of messages, in the spirit of public-inbox
+///
+/// The site is made of
+/// - `index.html`, the threads from the most recently active to the least
+/// - `m/<message-id>.html`, a page per message with links to its parent, replies and the
+///   previous and next messages
+/// - `s/<message-id>.html`, a page per patch series with every patch in order
+/// - `style.css`, the stylesheet generated from the theme
+pub struct Archive<'a, 'input> {
+    title: &'a str,
+    messages: &'a [Message<'input>],
+    threads: Threads,
+    /// The messages in the order of the threads, and the position of each message in it, for the
+    /// previous and next links
+    order: Vec<usize>,
+    positions: Vec<usize>,
+    pages: Vec<String>,
+    series: Vec<Option<Series>>,
+    options: Options,
+}
+
+impl<'a, 'input> Archive<'a, 'input> {
+    pub fn new(title: &'a str, messages: &'a [Message<'input>]) -> Self {
+        let threads = Threads::new(messages);
+        let mut names = HashSet::new();
+
+        let pages = messages
+            .iter()
+            .enumerate()
+            .map(|(index, message)| {
+                let base = message
+                    .message_id()
+                    .map(slug)
+                    .filter(|id| !id.is_empty())
+                    .unwrap_or_else(|| format!("message-{}", index + 1));
+
+                let mut name = base.clone();
+                let mut n = 1;
+
+                while !names.insert(name.clone()) {
+                    n += 1;
+                    name = format!("{}-{}", base, n);
+                }
+
+                name
+            })
+            .collect();
+
+        // Only threads with more than one patch are worth a page of their own
+        let series = (0..messages.len())
+            .map(|index| {
+                threads
+                    .series(messages, index)
+                    .filter(|series| threads.parent(index).is_none() && series.total > 1)
+            })
+            .collect();
+
+        let order = threads.order();
+        let mut positions = vec![0; messages.len()];
+
+        for (position, index) in order.iter().enumerate() {
+            positions[*index] = position;
+        }
+
+        Archive {
+            title,
+            messages,
+            threads,
+            order,
+            positions,
+            pages,
+            series,
+            options: Options::default(),
+        }
+    }
+
+    /// Renders the messages with `options` instead of the default ones
+    pub fn with_options(mut self, options: Options) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Writes every page of the site into `dir`, creating it when needed
+    pub fn write(&self, dir: &Path, theme: &Theme) -> Result<()> {
+        for sub in ["m", "s"] {
+            fs::create_dir_all(dir.join(sub))
+                .with_context(|| format!("Failed to create {}", dir.join(sub).display()))?;
+        }
+
+        let mut css = html::stylesheet(theme);
+        css.push_str(STYLESHEET);
+        write_file(&dir.join("style.css"), css.as_bytes())?;
+        write_file(&dir.join("index.html"), &self.index())?;
+
+        for index in 0..self.messages.len() {
+            let path = dir.join("m").join(format!("{}.html", self.pages[index]));
+            write_file(&path, &self.message(index)?)?;
+
+            if let Some(series) = &self.series[index] {
+                let path = dir.join("s").join(format!("{}.html", self.pages[index]));
+                write_file(&path, &self.series(index, series)?)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The index page, every thread as a tree of replies
+    pub fn index(&self) -> Vec<u8> {
+        let mut body = format!(
+            "<h1>{}</h1>\n<ul class=\"papr-threads\">\n",
+            escape(self.title)
+        );
+
+        for root in self.threads.roots() {
+            body.push_str(&self.tree(*root, None, "m/"));
+        }
+
+        body.push_str("</ul>\n");
+        page(self.title, "", &body)
+    }
+
+    /// The page of the message `index`
+    pub fn message(&self, index: usize) -> io::Result<Vec<u8>> {
+        let position = self.positions[index];
+        let root = self.threads.root(index);
+
+        let mut nav = vec![("index".to_string(), "../index.html".to_string())];
+        let mut link = |name: &str, index: Option<usize>| {
+            if let Some(index) = index {
+                nav.push((name.to_string(), format!("{}.html", self.pages[index])));
+            }
+        };
+
+        link("parent", self.threads.parent(index));
+        link("prev", position.checked_sub(1).map(|p| self.order[p]));
+        link("next", self.order.get(position + 1).copied());
+
+        if self.series[root].is_some() {
+            nav.push((
+                "series".to_string(),
+                format!("../s/{}.html", self.pages[root]),
+            ));
+        }
+
+        let mut body = navigation(&nav);
+        body.push_str(&self.rendered(index)?);
+
+        let replies = self.threads.children(index);
+
+        if !replies.is_empty() {
+            body.push_str("<h2>Replies</h2>\n<ul class=\"papr-threads\">\n");
+
+            for reply in replies {
+                let _ = writeln!(body, "<li>{}</li>", self.entry(*reply, Some(index), ""));
+            }
+
+            body.push_str("</ul>\n");
+        }
+
+        body.push_str("<h2>Thread</h2>\n<ul class=\"papr-threads\">\n");
+        body.push_str(&self.tree(root, Some(index), ""));
+        body.push_str("</ul>\n");
+
+        Ok(page(&self.subject(index), "../", &body))
+    }
+
+    /// The page of the series started by `root`, every patch one after the other
+    pub fn series(&self, root: usize, series: &Series) -> io::Result<Vec<u8>> {
+        let mut body = navigation(&[("index".to_string(), "../index.html".to_string())]);
+        let _ = writeln!(body, "<h1>{}</h1>", escape(&self.subject(root)));
+        body.push_str("<ol class=\"papr-series\">\n");
+
+        for patch in &series.patches {
+            let _ = writeln!(body, "<li>{}</li>", self.entry(*patch, None, "../m/"));
+        }
+
+        body.push_str("</ol>\n");
+
+        for index in series.cover.iter().chain(&series.patches) {
+            body.push_str(&self.rendered(*index)?);
+        }
+
+        Ok(page(&self.subject(root), "../", &body))
+    }
+
+    /// The list item of the thread starting at `index` with its replies nested in it. Without a
+    /// `current` message the tree is part of the index and series get a link
+    fn tree(&self, index: usize, current: Option<usize>, prefix: &str) -> String {
+        let mut tree = format!("<li>{}", self.entry(index, current, prefix));
+
+        if current.is_none() && self.series[index].is_some() {
+            let _ = write!(
+                tree,
+                " <a class=\"papr-series-link\" href=\"s/{}.html\">series</a>",
+                self.pages[index]
+            );
+        }
+
+        let replies = self.threads.children(index);
+
+        if !replies.is_empty() {
+            tree.push_str("\n<ul>\n");
+
+            for reply in replies {
+                tree.push_str(&self.tree(*reply, current, prefix));
+            }
+
+            tree.push_str("</ul>\n");
+        }
+
+        tree.push_str("</li>\n");
+        tree
+    }
+
+    /// A link to the message `index` followed by its author and date, `current` isn't linked.
+    /// `prefix` is the path from the page to the message pages
+    fn entry(&self, index: usize, current: Option<usize>, prefix: &str) -> String {
+        let message = &self.messages[index];
+        let subject = escape(&self.subject(index));
+
+        let mut entry = if current == Some(index) {
+            format!("<b>{}</b>", subject)
+        } else {
+            format!(
+                "<a href=\"{}{}.html\">{}</a>",
+                prefix, self.pages[index], subject
+            )
+        };
+
+        if let Some(from) = message.from() {
+            let _ = write!(
+                entry,
+                " <span class=\"papr-person_name\">{}</span>",
+                escape(from.name.unwrap_or(&from.email.to_string()))
+            );
+        }
+
+        if let Some(date) = message.date() {
+            let _ = write!(
+                entry,
+                " <span class=\"papr-date\">{}</span>",
+                date.format("%Y-%m-%d %H:%M")
+            );
+        }
+
+        entry
+    }
+
+    fn subject(&self, index: usize) -> String {
+        self.messages[index]
+            .subject()
+            .map(ToString::to_string)
+            .unwrap_or("(no subject)".to_string())
+    }
+
+    /// The message `index` rendered with its anchor
+    fn rendered(&self, index: usize) -> io::Result<String> {
+        let mut pre = format!(
+            "<article class=\"papr-message\" id=\"{0}\"><a class=\"papr-anchor\" href=\"#{0}\">#</a>\n<pre>",
+            self.pages[index]
+        );
+        let mut lines = Lines(Vec::new());
+        render::message(&self.messages[index], &self.options, &mut lines)?;
+
+        pre.push_str(&String::from_utf8_lossy(&lines.0));
+        pre.push_str("</pre></article>\n");
+        Ok(pre)
+    }
+}
+
+/// Collects the lines of a message as HTML, without the rest of the page
+struct Lines(Vec<u8>);
+
+impl Backend for Lines {
+    fn line(&mut self, spans: &[Span]) -> io::Result<()> {
+        html::write_line(&mut self.0, spans)
+    }
+}
+
+/// The rules the archive adds on top of the theme stylesheet
+const STYLESHEET: &str = "\
+.papr a { color: inherit; }
+.papr-nav { margin-bottom: 1em; }
+.papr-nav a { margin-right: 1em; }
+.papr-threads, .papr-threads ul { list-style: none; padding-left: 1.5em; }
+.papr-series-link { opacity: 0.6; }
+";
+
+/// A whole HTML page, `root` is the relative path to the top of the site
+fn page(title: &str, root: &str, body: &str) -> Vec<u8> {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n\
+         <link rel=\"stylesheet\" href=\"{}style.css\">\n</head>\n<body class=\"papr\">\n{}</body>\n</html>\n",
+        escape(title),
+        root,
+        body
+    )
+    .into_bytes()
+}
+
+fn navigation(links: &[(String, String)]) -> String {
+    let mut nav = String::from("<nav class=\"papr-nav\">");
+
+    for (name, href) in links {
+        let _ = write!(nav, "<a href=\"{}\">{}</a>", href, name);
+    }
+
+    nav.push_str("</nav>\n");
+    nav
+}
+
+fn write_file(path: &Path, content: &[u8]) -> Result<()> {
+    fs::File::create(path)
+        .and_then(|mut file| file.write_all(content))
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mailbox::Mailbox;
+
+    fn archive_page(page: io::Result<Vec<u8>>) -> String {
+        String::from_utf8(page.unwrap()).unwrap()
+    }
+
+    #[test]
+    fn archive_pages() {
+        let input = include_str!("mailbox/samples/thread.mbx");
+        let mailbox = Mailbox::try_from(input).unwrap();
+        let archive = Archive::new("netdev", &mailbox.messages);
+
+        let index = String::from_utf8(archive.index()).unwrap();
+        let standalone = index.find("mm: drop unused baz").unwrap();
+        let cover = index.find("net: fix foo handling").unwrap();
+        assert!(standalone < cover);
+        assert!(index.contains("href=\"m/20240603-foo-v2-1@example.com.html\""));
+        assert!(index.contains("href=\"s/20240603-foo-v2-0@example.com.html\">series</a>"));
+
+        let patch = archive_page(archive.message(1));
+        assert!(patch.contains("<a href=\"20240603-foo-v2-0@example.com.html\">parent</a>"));
+        assert!(patch.contains("<a href=\"20240603-foo-v2-0@example.com.html\">prev</a>"));
+        assert!(patch.contains("<a href=\"ZmBob-review@kernel.org.html\">next</a>"));
+        assert!(patch.contains("<a href=\"../s/20240603-foo-v2-0@example.com.html\">series</a>"));
+        assert!(patch.contains("<b>[PATCH v2 1/2] net: fix foo</b>"));
+        assert!(patch.contains("<h2>Replies</h2>"));
+
+        let series = archive.series[0].as_ref().unwrap();
+        let series = archive_page(archive.series(0, series));
+        let first = series.find("id=\"20240603-foo-v2-1@example.com\"").unwrap();
+        let second = series.find("id=\"20240603-foo-v2-2@example.com\"").unwrap();
+        assert!(first < second);
+        assert!(!series.contains("ZmBob-review"));
+
+        assert!(archive.series[4].is_none());
+    }
+}
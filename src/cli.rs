@@ -1,4 +1,9 @@
-use clap::Parser;
+use std::{env, path::PathBuf};
+
+use clap::{Parser, Subcommand, ValueEnum};
+use regex::Regex;
+
+use crate::select::Selection;
 
 #[derive(Debug, Parser)]
 #[command(version, about, long_about)]
@@ -9,10 +14,320 @@ use clap::Parser;
 ///
 /// papr has some special highlighting for email files that are patches (diffs).
 ///
+/// Without a command papr runs `show`, so `papr file.mbx` is the same as `papr show file.mbx`.
+#[command(args_conflicts_with_subcommands = true)]
 pub struct Args {
-    /// The files to apply syntax highlighting to
-    pub files: Vec<String>,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    #[command(flatten)]
+    pub show: ShowArgs,
+}
+
+impl Args {
+    /// The command to run, `show` when none was given
+    pub fn command(self) -> Command {
+        self.command.unwrap_or(Command::Show(self.show))
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Highlight the messages of mailboxes (default)
+    Show(ShowArgs),
+    /// List the messages one per line: number, date, author and subject. Replies are marked with
+    /// `↳` and messages carrying review trailers with `✓`
+    List(Common),
+    /// Show the messages as trees of replies, built from their `Message-Id`, `In-Reply-To` and
+    /// `References` headers
+    Thread(Common),
+    /// Count the messages, patches, series and authors of mailboxes
+    Stats(Common),
+    /// Check the patches before sending them, like the kernel's `checkpatch.pl`: sign-offs,
+    /// subjects, trailers and series. Exits with 1 when a problem is found
+    Lint(LintArgs),
+    /// Generate a static website from mailboxes: an index of threads, a page per message and a
+    /// page per patch series
+    Archive {
+        /// The mailboxes to archive, STDIN is read when none is given
+        files: Vec<String>,
+        /// The directory where the site is written, it is created when missing
+        #[clap(short, long)]
+        output: PathBuf,
+        /// The title of the index page, defaults to the name of the first mailbox
+        #[clap(long)]
+        title: Option<String>,
+        /// The color theme of the pages, defaults to `light`
+        #[clap(long)]
+        theme: Option<String>,
+        #[command(flatten)]
+        diff: DiffArgs,
+    },
+    /// Write each message to its own file, like `git mailsplit`. The paths of the files are
+    /// written to STDOUT
+    Split(SplitArgs),
+    /// Merge mailboxes into a single mbox, dropping the messages found more than once. What was
+    /// dropped is reported on STDERR
+    Merge {
+        /// The mailboxes to merge, STDIN is read when none is given
+        files: Vec<String>,
+        /// The file where the merged mailbox is written, STDOUT when not given
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+        /// The order of the merged messages
+        #[clap(long, value_enum, default_value_t = SortOrder::Date)]
+        sort: SortOrder,
+        /// Don't report the dropped messages
+        #[clap(short, long)]
+        quiet: bool,
+    },
+}
+
+#[derive(Debug, clap::Args)]
+pub struct ShowArgs {
+    #[command(flatten)]
+    pub common: Common,
     /// Reduce messages to show only it's frontmatter
     #[clap(short, long)]
     pub frontmatter: bool,
+    /// Show the commit message of patches followed by a diffstat instead of their diff, with
+    /// bars scaled to the width of the terminal
+    #[clap(long, conflicts_with = "frontmatter")]
+    pub stat: bool,
+    /// Only show the diffstat of all the patches of each mailbox together
+    #[clap(long, conflicts_with_all = ["frontmatter", "stat"])]
+    pub stat_only: bool,
+    #[command(flatten)]
+    pub diff: DiffArgs,
+}
+
+/// Options changing how the diffs of patches are rendered. They override the settings of the
+/// config file
+#[derive(Debug, Default, Clone, clap::Args)]
+#[command(next_help_heading = "Diffs")]
+pub struct DiffArgs {
+    /// The whitespace errors highlighted in added lines, like `-crlf` or `none`
+    #[clap(long, value_name = "RULES")]
+    pub whitespace: Option<papr::whitespace::Rules>,
+    /// How a removed line and the added line replacing it are compared to emphasize the words
+    /// that changed
+    #[clap(long, value_enum, value_name = "MODE")]
+    pub word_diff: Option<WordDiff>,
+    /// Compare changed lines by the matches of this regular expression, like
+    /// `git diff --word-diff-regex`
+    #[clap(long, value_name = "REGEX", value_parser = Regex::new)]
+    pub word_diff_regex: Option<Regex>,
+    /// Don't highlight the syntax of the code in diffs, which is faster on large patches
+    #[clap(long)]
+    pub no_syntax: bool,
+    /// Show the old and new lines of hunks in two columns using the width of the terminal,
+    /// hunks are unified when it is too narrow
+    #[clap(short = 'y', long)]
+    pub side_by_side: bool,
+    /// Start the lines of hunks with their numbers in the old and the new file
+    #[clap(short = 'n', long)]
+    pub line_numbers: bool,
+    /// Show the encoded payloads of binary patches instead of collapsing them
+    #[clap(long)]
+    pub binary: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum WordDiff {
+    /// Words, runs of whitespace and single punctuation characters
+    Word,
+    Char,
+    /// Don't compare the lines
+    None,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct LintArgs {
+    #[command(flatten)]
+    pub common: Common,
+    /// Only run this check, like `signed-off-by`, `co-developed-by` and `sign-off-order` for the
+    /// Developer Certificate of Origin. Every check runs by default
+    #[clap(long, value_name = "CHECK")]
+    pub check: Vec<papr::lint::Check>,
+    /// The whitespace errors counted by the `whitespace` check, like `-crlf` or `none`.
+    /// Overrides the `whitespace` set in the config file
+    #[clap(long, value_name = "RULES")]
+    pub whitespace: Option<papr::whitespace::Rules>,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct SplitArgs {
+    /// The mailboxes to split, STDIN is read when none is given
+    pub files: Vec<String>,
+    /// The directory where the files are written, it is created when missing
+    #[clap(short, long)]
+    pub output: PathBuf,
+    /// How the files are named
+    #[clap(long, value_enum, default_value_t = Naming::Sequence)]
+    pub name: Naming,
+    /// Keep the `From ` line starting each message
+    #[clap(long)]
+    pub keep_from_line: bool,
+    /// Turn the `>From ` lines escaped in the mailbox back into `From ` lines (mboxrd)
+    #[clap(long)]
+    pub unescape_from: bool,
+    /// Only write the patches, skipping cover letters, replies and other messages
+    #[clap(long)]
+    pub patches_only: bool,
+    #[command(flatten)]
+    pub selection: Selection,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Naming {
+    /// `0001`, `0002`... in the order of the messages
+    Sequence,
+    /// The index and the subject of patches, like `0003-net-fix-foo.patch`. Other messages use
+    /// their number and end with `.eml`
+    Subject,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SortOrder {
+    /// The oldest messages first
+    Date,
+    /// Threads one after the other, the most recently active first, replies after what they
+    /// answer
+    Thread,
+    /// The order of the input files
+    Input,
+}
+
+/// The options shared by every command that reads mailboxes and writes to the terminal
+#[derive(Debug, clap::Args)]
+pub struct Common {
+    /// The mailbox files to read, STDIN is read when none is given
+    pub files: Vec<String>,
+    /// When to color the output. `auto` colors only when writing to a terminal and honors the
+    /// `NO_COLOR`, `CLICOLOR` and `CLICOLOR_FORCE` environment variables
+    #[clap(long, value_enum, default_value_t = ColorMode::Auto)]
+    pub color: ColorMode,
+    /// The color theme, one of `dark`, `light` or `monochrome`. Overrides the theme set in
+    /// `~/.config/papr/config.toml`
+    #[clap(long)]
+    pub theme: Option<String>,
+    /// Never pipe the output through a pager. By default papr pages through `$PAPR_PAGER`,
+    /// `$PAGER` or `less -R` when the output doesn't fit the terminal
+    #[clap(long)]
+    pub no_pager: bool,
+    /// The output format
+    #[clap(short, long, value_enum, default_value_t = OutputFormat::Text)]
+    pub output: OutputFormat,
+    #[command(flatten)]
+    pub selection: Selection,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Highlighted text, colored according to `--color`
+    Text,
+    /// A self-contained HTML page, themed with the `light` theme unless another one is picked
+    Html,
+    /// The messages as they are in the input, to save them in a mbox file
+    Mbox,
+    /// A single JSON document with every mailbox
+    #[cfg(feature = "json")]
+    Json,
+    /// One JSON document per message and line (JSON Lines)
+    #[cfg(feature = "json")]
+    Jsonl,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ColorMode {
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorMode {
+    /// Whether the output should be colored, `is_terminal` tells if the output is a terminal
+    pub fn enabled(self, is_terminal: bool) -> bool {
+        self.enabled_with(is_terminal, |name| env::var(name).ok())
+    }
+
+    fn enabled_with(self, is_terminal: bool, var: impl Fn(&str) -> Option<String>) -> bool {
+        match self {
+            ColorMode::Always => true,
+            ColorMode::Never => false,
+            ColorMode::Auto => {
+                // See https://no-color.org and https://bixense.com/clicolors
+                let set = |name| var(name).is_some_and(|value| !value.is_empty());
+                let enabled = |name| var(name).is_some_and(|value| value != "0");
+
+                if set("NO_COLOR") {
+                    false
+                } else if set("CLICOLOR_FORCE") && enabled("CLICOLOR_FORCE") {
+                    true
+                } else if var("CLICOLOR").is_some_and(|value| value == "0") {
+                    false
+                } else {
+                    is_terminal
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env<'a>(vars: &'a [(&'a str, &'a str)]) -> impl Fn(&str) -> Option<String> + 'a {
+        move |name| {
+            vars.iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value.to_string())
+        }
+    }
+
+    #[test]
+    fn show_is_default() {
+        let command = Args::try_parse_from(["papr", "-f", "a.mbx", "b.mbx"])
+            .unwrap()
+            .command();
+        let Command::Show(show) = command else {
+            panic!("expected `show`, got {:?}", command);
+        };
+        assert!(show.frontmatter);
+        assert_eq!(show.common.files, ["a.mbx", "b.mbx"]);
+
+        let command = Args::try_parse_from(["papr", "show", "--no-pager", "a.mbx"])
+            .unwrap()
+            .command();
+        assert!(matches!(command, Command::Show(show) if show.common.no_pager));
+
+        let command = Args::try_parse_from(["papr", "stats", "--color", "never", "a.mbx"])
+            .unwrap()
+            .command();
+        assert!(matches!(command, Command::Stats(common) if common.color == ColorMode::Never));
+
+        // Options of `show` don't belong to other commands
+        assert!(Args::try_parse_from(["papr", "thread", "-f"]).is_err());
+    }
+
+    #[test]
+    fn color_mode_explicit() {
+        assert!(ColorMode::Always.enabled_with(false, env(&[("NO_COLOR", "1")])));
+        assert!(!ColorMode::Never.enabled_with(true, env(&[("CLICOLOR_FORCE", "1")])));
+    }
+
+    #[test]
+    fn color_mode_auto() {
+        assert!(ColorMode::Auto.enabled_with(true, env(&[])));
+        assert!(!ColorMode::Auto.enabled_with(false, env(&[])));
+        assert!(!ColorMode::Auto.enabled_with(true, env(&[("NO_COLOR", "1")])));
+        assert!(ColorMode::Auto.enabled_with(true, env(&[("NO_COLOR", "")])));
+        assert!(ColorMode::Auto.enabled_with(false, env(&[("CLICOLOR_FORCE", "1")])));
+        assert!(!ColorMode::Auto.enabled_with(false, env(&[("CLICOLOR_FORCE", "0")])));
+        assert!(!ColorMode::Auto.enabled_with(true, env(&[("CLICOLOR", "0")])));
+        assert!(!ColorMode::Auto.enabled_with(
+            false,
+            env(&[("NO_COLOR", "1"), ("CLICOLOR_FORCE", "1")])
+        ));
+    }
 }
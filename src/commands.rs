@@ -0,0 +1,173 @@
+use std::{
+    io::{stdin, stdout, BufWriter, IsTerminal, Read, Write},
+    path::Path,
+};
+
+use color_eyre::eyre::{bail, Context, Result};
+use papr::{
+    mailbox::Mailbox,
+    render::{
+        ansi::{Ansi, MARKS_PATTERN},
+        html::Html,
+        plain::Plain,
+        theme::Theme,
+        Backend,
+    },
+};
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+use crate::{
+    cli::{Common, OutputFormat},
+    config::Config,
+    pager::{self, Pager},
+};
+
+pub mod archive;
+pub mod lint;
+pub mod list;
+pub mod merge;
+pub mod show;
+pub mod split;
+pub mod stats;
+pub mod thread;
+
+/// Reads every file in `files`, or STDIN if no files are provided
+pub fn read_input(files: &[String]) -> Result<Vec<(String, String)>> {
+    if files.is_empty() {
+        let mut content = String::new();
+
+        stdin()
+            .read_to_string(&mut content)
+            .with_context(|| "Failed to read input from STDIN")?;
+
+        Ok(vec![("STDIN".to_string(), content)])
+    } else {
+        read_all(files).with_context(|| "While opening files")
+    }
+}
+
+fn read_all(files: &[String]) -> Result<Vec<(String, String)>> {
+    files
+        .into_par_iter()
+        .map(|file| {
+            let path = Path::new(&file);
+            let content = std::fs::read_to_string(path)
+                .with_context(|| format!("File {} could not be opened", &path.display()))?;
+
+            Ok((file.clone(), content))
+        })
+        .collect()
+}
+
+/// Parses the mailbox of every file read by [`read_input`]
+pub fn parse(files: &[(String, String)]) -> Result<Vec<(&str, Mailbox<'_>)>> {
+    files
+        .iter()
+        .map(|(path, content)| Ok((path.as_str(), Mailbox::try_from(content.as_str())?)))
+        .collect()
+}
+
+/// Parses the mailbox of every file and keeps only the messages picked by the selection
+/// options
+pub fn select<'a>(
+    common: &Common,
+    files: &'a [(String, String)],
+) -> Result<Vec<(&'a str, Mailbox<'a>)>> {
+    let mut mailboxes = parse(files)?;
+
+    for (_, mailbox) in &mut mailboxes {
+        common.selection.retain(mailbox);
+    }
+
+    Ok(mailboxes)
+}
+
+/// Sets up where the output of a command goes according to the `common` options and calls
+/// `write` with it. `write` also gets the theme to color the output with, if any, and whether
+/// search marks should be emitted for the pager
+pub fn output(
+    common: &Common,
+    write: impl FnOnce(Box<dyn Write + '_>, Option<Theme>, bool) -> Result<()>,
+) -> Result<()> {
+    let is_terminal = stdout().is_terminal();
+    let pager = if is_terminal && !common.no_pager {
+        Pager::from_env()
+    } else {
+        None
+    };
+    let colored = common.output == OutputFormat::Text && common.color.enabled(is_terminal);
+    let marks = colored && pager.as_ref().is_some_and(Pager::is_less);
+
+    let theme = match common.output {
+        OutputFormat::Text if colored => {
+            Some(Config::load()?.theme(common.theme.as_deref(), "dark")?)
+        }
+        OutputFormat::Html => Some(Config::load()?.theme(common.theme.as_deref(), "light")?),
+        _ => None,
+    };
+
+    // When a pager may be used the output is buffered to find out whether it fits the terminal
+    let mut buffer = Vec::new();
+    let out: Box<dyn Write> = if pager.is_some() {
+        Box::new(&mut buffer)
+    } else {
+        Box::new(BufWriter::new(stdout().lock()))
+    };
+
+    write(out, theme, marks)?;
+
+    if let Some(pager) = pager {
+        if pager::is_taller_than_terminal(&buffer) {
+            let args = if marks {
+                vec![format!("--pattern={}", MARKS_PATTERN)]
+            } else {
+                vec![]
+            };
+
+            pager.page(&buffer, &args)?;
+        } else {
+            stdout()
+                .write_all(&buffer)
+                .with_context(|| "Failed to write to STDOUT")?;
+        }
+    }
+
+    Ok(())
+}
+
+/// What the output of a command is written with
+pub enum Sink<'a> {
+    /// A backend rendering spans, for text and HTML
+    Spans(Box<dyn Backend + 'a>),
+    /// The output itself, for the formats that aren't made of spans
+    Raw(Box<dyn Write + 'a>),
+}
+
+impl<'a> Sink<'a> {
+    /// The sink for the `output` format, text is colored with `theme` when one is given
+    pub fn new(
+        out: Box<dyn Write + 'a>,
+        output: OutputFormat,
+        theme: Option<Theme>,
+        marks: bool,
+    ) -> Self {
+        match output {
+            OutputFormat::Text => Sink::Spans(match theme {
+                Some(theme) => Box::new(Ansi::new(out, theme).with_marks(marks)),
+                None => Box::new(Plain::new(out)),
+            }),
+            OutputFormat::Html => {
+                Sink::Spans(Box::new(Html::new(out, theme.unwrap_or_else(Theme::light))))
+            }
+            _ => Sink::Raw(out),
+        }
+    }
+
+    /// The backend of the sink, for commands that can only write spans
+    pub fn spans(self, command: &str) -> Result<Box<dyn Backend + 'a>> {
+        match self {
+            Sink::Spans(backend) => Ok(backend),
+            Sink::Raw(_) => bail!("`papr {}` only writes text or HTML", command),
+        }
+    }
+}
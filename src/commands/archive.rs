@@ -0,0 +1,37 @@
+use std::path::Path;
+
+use color_eyre::eyre::Result;
+use papr::{archive::Archive, mailbox::Mailbox};
+
+use crate::{cli::DiffArgs, config::Config};
+
+/// Writes the static site of the messages in `files` into `dir`
+pub fn run(
+    files: Vec<String>,
+    dir: &Path,
+    title: Option<String>,
+    theme: Option<String>,
+    diff: &DiffArgs,
+) -> Result<()> {
+    let files = super::read_input(&files)?;
+    let title = title.unwrap_or_else(|| {
+        let path = Path::new(&files[0].0);
+        path.file_name()
+            .unwrap_or(path.as_os_str())
+            .to_string_lossy()
+            .to_string()
+    });
+    let config = Config::load()?;
+    let theme = config.theme(theme.as_deref(), "light")?;
+    let options = config.options(diff)?;
+
+    let mut messages = Vec::new();
+
+    for (_, content) in &files {
+        messages.extend(Mailbox::try_from(content.as_str())?.messages);
+    }
+
+    Archive::new(&title, &messages)
+        .with_options(options)
+        .write(dir, &theme)
+}
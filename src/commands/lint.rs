@@ -0,0 +1,243 @@
+use color_eyre::eyre::{bail, Context, Result};
+use papr::{
+    lint::{self, Problem},
+    mailbox::Mailbox,
+    render::{self, Role, Span},
+    whitespace::Rules,
+};
+
+use super::Sink;
+use crate::{cli::LintArgs, config::Config};
+
+/// Checks the patches of the mailboxes and exits with an error code when any has a problem
+pub fn run(args: LintArgs) -> Result<()> {
+    let LintArgs {
+        common,
+        check,
+        whitespace,
+    } = args;
+    let whitespace = Config::load()?.whitespace(whitespace)?;
+    let files = super::read_input(&common.files)?;
+    let mailboxes = super::parse(&files)?;
+    let reports = mailboxes
+        .iter()
+        .map(|(path, mailbox)| {
+            let indexes = common.selection.indexes(mailbox);
+            let mut report = Report::new(path, mailbox, &indexes, whitespace);
+
+            if !check.is_empty() {
+                report
+                    .problems
+                    .retain(|problem| check.contains(&problem.check));
+            }
+
+            report
+        })
+        .collect::<Vec<_>>();
+
+    super::output(&common, |out, theme, marks| {
+        match Sink::new(out, common.output, theme, marks) {
+            Sink::Spans(mut backend) => {
+                for report in &reports {
+                    backend.begin_mailbox(report.path)?;
+
+                    for line in report.lines() {
+                        backend
+                            .line(&line)
+                            .with_context(|| "Failed to write to STDOUT")?;
+                    }
+
+                    backend.end_message()?;
+                }
+
+                backend
+                    .finish()
+                    .with_context(|| "Failed to write to STDOUT")
+            }
+            #[cfg(feature = "json")]
+            Sink::Raw(mut out) if common.output == crate::cli::OutputFormat::Json => {
+                let entries = reports.iter().flat_map(Report::entries).collect::<Vec<_>>();
+                serde_json::to_writer_pretty(&mut out, &entries)
+                    .with_context(|| "Failed to write JSON to STDOUT")?;
+                writeln!(out).with_context(|| "Failed to write to STDOUT")
+            }
+            #[cfg(feature = "json")]
+            Sink::Raw(mut out) if common.output == crate::cli::OutputFormat::Jsonl => {
+                for entry in reports.iter().flat_map(Report::entries) {
+                    serde_json::to_writer(&mut out, &entry)
+                        .with_context(|| "Failed to write JSON to STDOUT")?;
+                    writeln!(out).with_context(|| "Failed to write to STDOUT")?;
+                }
+
+                out.flush().with_context(|| "Failed to write to STDOUT")
+            }
+            Sink::Raw(_) => bail!("`papr lint` only writes text, HTML, JSON or JSON Lines"),
+        }
+    })?;
+
+    if reports.iter().any(|report| !report.problems.is_empty()) {
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
+
+/// The problems of the messages picked in a mailbox. The whole mailbox is checked so that
+/// series are checked whole
+struct Report<'a> {
+    path: &'a str,
+    mailbox: &'a Mailbox<'a>,
+    /// How many patches were checked
+    patches: usize,
+    problems: Vec<Problem>,
+}
+
+/// A problem as written in JSON, with the file and the number of its message
+#[cfg(feature = "json")]
+#[derive(serde::Serialize)]
+struct Entry<'a> {
+    file: &'a str,
+    message: usize,
+    subject: Option<String>,
+    check: lint::Check,
+    description: &'a str,
+}
+
+impl<'a> Report<'a> {
+    fn new(path: &'a str, mailbox: &'a Mailbox<'a>, indexes: &[usize], whitespace: Rules) -> Self {
+        let mut problems = lint::lint(&mailbox.messages, whitespace);
+        problems.retain(|problem| indexes.contains(&problem.message));
+
+        Report {
+            path,
+            mailbox,
+            patches: indexes
+                .iter()
+                .filter(|index| mailbox.messages[**index].is_patch())
+                .count(),
+            problems,
+        }
+    }
+
+    /// The messages with problems, each followed by its problems, and a summary
+    fn lines(&self) -> Vec<Vec<Span<'_>>> {
+        let mut lines = Vec::new();
+        let width = self.mailbox.messages.len().to_string().len();
+        let name_width = lint::Check::ALL
+            .iter()
+            .map(|check| check.name().len())
+            .max()
+            .unwrap_or(0);
+
+        for (i, problem) in self.problems.iter().enumerate() {
+            let message = &self.mailbox.messages[problem.message];
+
+            if i == 0 || self.problems[i - 1].message != problem.message {
+                let mut line = vec![
+                    Span::new(
+                        Role::Separator,
+                        format!("{:>1$}", problem.message + 1, width),
+                    ),
+                    Span::text(" "),
+                ];
+
+                match message.subject() {
+                    Some(subject) => line.extend(render::subject_spans(subject)),
+                    None => line.push(Span::text("(no subject)")),
+                }
+
+                lines.push(line);
+            }
+
+            lines.push(vec![
+                Span::text(" ".repeat(width + 1)),
+                Span::new(
+                    Role::Removed,
+                    format!("{:<1$}", problem.check.name(), name_width),
+                ),
+                Span::text(" "),
+                Span::text(problem.description.as_str()),
+            ]);
+        }
+
+        let failing = self
+            .problems
+            .iter()
+            .enumerate()
+            .filter(|(i, problem)| *i == 0 || self.problems[i - 1].message != problem.message)
+            .count();
+        let summary = match self.problems.len() {
+            0 => format!("no problems in {} patches", self.patches),
+            n => format!(
+                "{} problem{} in {} of {} patches",
+                n,
+                if n == 1 { "" } else { "s" },
+                failing,
+                self.patches
+            ),
+        };
+        lines.push(vec![Span::new(
+            if self.problems.is_empty() {
+                Role::Added
+            } else {
+                Role::Removed
+            },
+            summary,
+        )]);
+
+        lines
+    }
+
+    #[cfg(feature = "json")]
+    fn entries(&self) -> impl Iterator<Item = Entry<'_>> {
+        self.problems.iter().map(|problem| Entry {
+            file: self.path,
+            message: problem.message + 1,
+            subject: self.mailbox.messages[problem.message]
+                .subject()
+                .map(ToString::to_string),
+            check: problem.check,
+            description: &problem.description,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(report: &Report) -> Vec<String> {
+        report
+            .lines()
+            .iter()
+            .map(|line| line.iter().map(|span| span.text.as_ref()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn lint_report() {
+        let input = include_str!("../mailbox/samples/lint.mbx");
+        let mailbox = Mailbox::try_from(input).unwrap();
+
+        let report = Report::new("lint.mbx", &mailbox, &[1, 2], Rules::default());
+        assert_eq!(
+            text(&report),
+            [
+                "2 [PATCH v4 2/3] drm: add a widget helper",
+                "  signed-off-by   no `Signed-off-by`",
+                "  trailer-format  malformed trailer `Signed-off-by:Dave Brown <dave@example.com>`, expected `Key: value`",
+                "  change-id       remove the Gerrit `Change-Id: I0123456789abcdef0123456789abcdef01234567`",
+                "  series-version  v4 in a series of v3",
+                "3 [PATCH v3 2/5] drm: use the widget helper",
+                "  trailer-format  the trailers aren't separated from the message by an empty line",
+                "  series-index    2/5 in a series of 3 patches",
+                "6 problems in 2 of 2 patches",
+            ]
+        );
+
+        let input = include_str!("../mailbox/samples/thread.mbx");
+        let mailbox = Mailbox::try_from(input).unwrap();
+        let report = Report::new("thread.mbx", &mailbox, &[0, 1, 2, 3, 4], Rules::default());
+        assert_eq!(text(&report), ["no problems in 4 patches"]);
+    }
+}
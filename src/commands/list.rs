@@ -0,0 +1,149 @@
+use color_eyre::eyre::{Context, Result};
+use papr::{
+    mailbox::{message::Message, Mailbox},
+    render::{self, Role, Span},
+};
+
+use super::Sink;
+use crate::cli::Common;
+
+/// The longest author name shown, longer names are cut
+const NAME_WIDTH: usize = 24;
+
+/// Writes one line per message, like `git log --oneline`
+pub fn run(common: Common) -> Result<()> {
+    let files = super::read_input(&common.files)?;
+    let mailboxes = super::parse(&files)?;
+
+    super::output(&common, |out, theme, marks| {
+        let mut backend = Sink::new(out, common.output, theme, marks).spans("list")?;
+
+        for (path, mailbox) in &mailboxes {
+            backend.begin_mailbox(path)?;
+
+            for line in lines(mailbox, &common.selection.indexes(mailbox)) {
+                backend
+                    .line(&line)
+                    .with_context(|| "Failed to write to STDOUT")?;
+            }
+
+            backend.end_message()?;
+        }
+
+        backend
+            .finish()
+            .with_context(|| "Failed to write to STDOUT")
+    })
+}
+
+/// The line of the messages at `indexes`: their number, date, author, markers and subject. A `↳`
+/// marks replies and a `✓` messages with `Reviewed-by`, `Acked-by` or `Tested-by` trailers
+fn lines<'a>(mailbox: &'a Mailbox, indexes: &[usize]) -> Vec<Vec<Span<'a>>> {
+    let width = mailbox.messages.len().to_string().len();
+    let messages = indexes
+        .iter()
+        .map(|index| (*index, &mailbox.messages[*index]));
+    let names = messages.clone().map(|(_, message)| author(message));
+    let name_width = names
+        .clone()
+        .map(|name| name.chars().count().min(NAME_WIDTH))
+        .max()
+        .unwrap_or(0);
+
+    messages
+        .zip(names)
+        .map(|((index, message), name)| {
+            let date = message
+                .date()
+                .map(|date| date.format("%Y-%m-%d").to_string())
+                .unwrap_or(" ".repeat(10));
+            let reviewed = message
+                .body
+                .trailers()
+                .iter()
+                .any(|(key, _)| render::trailer_role(key) == Role::Review);
+
+            let mut line = vec![
+                Span::new(Role::Separator, format!("{:>1$}", index + 1, width)),
+                Span::text(" "),
+                Span::new(Role::Date, date),
+                Span::text(" "),
+                Span::new(Role::PersonName, fit(&name, name_width)),
+                Span::text(" "),
+                Span::new(Role::Separator, if message.is_reply() { "↳" } else { " " }),
+                Span::new(Role::Review, if reviewed { "✓" } else { " " }),
+                Span::text(" "),
+            ];
+
+            match message.subject() {
+                Some(subject) => line.extend(render::subject_spans(subject)),
+                None => line.push(Span::text("(no subject)")),
+            }
+
+            line
+        })
+        .collect()
+}
+
+/// The name of the author of `message`, or its address when it has no name
+fn author(message: &Message) -> String {
+    match message.from() {
+        Some(from) => from
+            .name
+            .map(str::to_string)
+            .unwrap_or(from.email.to_string()),
+        None => String::new(),
+    }
+}
+
+/// `text` cut or padded to `width` characters
+fn fit(text: &str, width: usize) -> String {
+    if text.chars().count() > width {
+        let mut cut = text.chars().take(width - 1).collect::<String>();
+        cut.push('…');
+        cut
+    } else {
+        format!("{:<1$}", text, width)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn listed(mailbox: &Mailbox, indexes: &[usize]) -> Vec<String> {
+        lines(mailbox, indexes)
+            .iter()
+            .map(|line| line.iter().map(|span| span.text.as_ref()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn list_thread() {
+        let input = include_str!("../mailbox/samples/thread.mbx");
+        let mailbox = Mailbox::try_from(input).unwrap();
+
+        assert_eq!(
+            listed(&mailbox, &[0, 1, 2, 3, 4]),
+            [
+                "1 2024-06-03 Alice Smith    [PATCH v2 0/2] net: fix foo handling",
+                "2 2024-06-03 Alice Smith    [PATCH v2 1/2] net: fix foo",
+                "3 2024-06-03 Alice Smith    [PATCH v2 2/2] net: add bar helper",
+                "4 2024-06-04 Bob Jones   ↳✓ Re: [PATCH v2 1/2] net: fix foo",
+                "5 2024-06-05 Carol White    [PATCH] mm: drop unused baz",
+            ]
+        );
+
+        // Selected messages keep their number
+        assert_eq!(
+            listed(&mailbox, &[3]),
+            ["4 2024-06-04 Bob Jones ↳✓ Re: [PATCH v2 1/2] net: fix foo"]
+        );
+    }
+
+    #[test]
+    fn fit_names() {
+        assert_eq!(fit("Alice", 7), "Alice  ");
+        assert_eq!(fit("Alexandra", 5), "Alex…");
+    }
+}
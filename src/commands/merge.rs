@@ -0,0 +1,288 @@
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    fs::File,
+    hash::{DefaultHasher, Hash, Hasher},
+    io::{self, stderr, stdout, BufWriter, Write},
+    path::Path,
+};
+
+use color_eyre::eyre::{Context, Result};
+use papr::mailbox::{self, message::Message, thread::Threads, Mailbox};
+
+use crate::cli::SortOrder;
+
+/// Writes the messages of every mailbox once, in the `sort` order, to `output` or STDOUT
+pub fn run(files: Vec<String>, output: Option<&Path>, sort: SortOrder, quiet: bool) -> Result<()> {
+    let files = super::read_input(&files)?;
+    let mailboxes = super::parse(&files)?;
+    let merge = Merge::new(&mailboxes);
+
+    let mut out: Box<dyn Write> = match output {
+        Some(path) => {
+            Box::new(BufWriter::new(File::create(path).with_context(|| {
+                format!("Failed to create {}", path.display())
+            })?))
+        }
+        None => Box::new(BufWriter::new(stdout().lock())),
+    };
+
+    mailbox::write_mbox(&mut out, merge.sorted(sort))
+        .and_then(|_| out.flush())
+        .with_context(|| "Failed to write the merged mailbox")?;
+
+    if !quiet {
+        merge
+            .report(&mut stderr().lock())
+            .with_context(|| "Failed to write to STDERR")?;
+    }
+
+    Ok(())
+}
+
+/// Where a message was read: the path of its mailbox and its number there, from 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Origin<'a> {
+    path: &'a str,
+    number: usize,
+}
+
+/// What makes two messages the same: their `Message-Id`, or their content when they have none
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Key {
+    Id(String),
+    Content(u64),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Dropped<'a> {
+    origin: Origin<'a>,
+    /// The index of the message kept in its place
+    kept: usize,
+    key: Key,
+}
+
+/// The messages of several mailboxes, each kept once in the order they were first found
+#[derive(Debug)]
+struct Merge<'a> {
+    messages: Vec<Message<'a>>,
+    origins: Vec<Origin<'a>>,
+    dropped: Vec<Dropped<'a>>,
+}
+
+impl<'a> Merge<'a> {
+    fn new(mailboxes: &[(&'a str, Mailbox<'a>)]) -> Self {
+        let mut merge = Merge {
+            messages: Vec::new(),
+            origins: Vec::new(),
+            dropped: Vec::new(),
+        };
+        let mut seen = HashMap::new();
+
+        for (path, mailbox) in mailboxes {
+            for (index, message) in mailbox.messages.iter().enumerate() {
+                let origin = Origin {
+                    path,
+                    number: index + 1,
+                };
+
+                match seen.entry(key(message)) {
+                    Entry::Occupied(entry) => merge.dropped.push(Dropped {
+                        origin,
+                        kept: *entry.get(),
+                        key: entry.key().clone(),
+                    }),
+                    Entry::Vacant(entry) => {
+                        entry.insert(merge.messages.len());
+                        merge.messages.push(message.clone());
+                        merge.origins.push(origin);
+                    }
+                }
+            }
+        }
+
+        merge
+    }
+
+    /// The kept messages in the `sort` order. Messages without a date come last when sorting by
+    /// date
+    fn sorted(&self, sort: SortOrder) -> Vec<&Message<'a>> {
+        let order = match sort {
+            SortOrder::Date => {
+                let mut order = (0..self.messages.len()).collect::<Vec<_>>();
+                order.sort_by_key(|index| {
+                    let date = self.messages[*index].date();
+                    (date.is_none(), date)
+                });
+                order
+            }
+            SortOrder::Thread => Threads::new(&self.messages).order(),
+            SortOrder::Input => (0..self.messages.len()).collect(),
+        };
+
+        order
+            .into_iter()
+            .map(|index| &self.messages[index])
+            .collect()
+    }
+
+    /// Writes how many messages were kept and which ones were dropped
+    fn report(&self, out: &mut impl Write) -> io::Result<()> {
+        let total = self.messages.len() + self.dropped.len();
+
+        if self.dropped.is_empty() {
+            return writeln!(out, "Kept {} of {} messages, no duplicates", total, total);
+        }
+
+        writeln!(
+            out,
+            "Kept {} of {} messages, dropped {}:",
+            self.messages.len(),
+            total,
+            self.dropped.len()
+        )?;
+
+        for dropped in &self.dropped {
+            let kept = self.origins[dropped.kept];
+            let (what, reason) = match &dropped.key {
+                Key::Id(id) => (format!("<{}>", id), "same Message-Id"),
+                Key::Content(_) => {
+                    let subject = self.messages[dropped.kept]
+                        .subject()
+                        .map(|subject| subject.to_string())
+                        .unwrap_or_default();
+                    (format!("\"{}\"", subject), "same content")
+                }
+            };
+
+            writeln!(
+                out,
+                "  {} #{} {} ({} as {} #{})",
+                dropped.origin.path, dropped.origin.number, what, reason, kept.path, kept.number
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The `Message-Id` of the message, or a hash of its author, date, subject and body. The other
+/// headers are left out as they change with the way the message was received
+fn key(message: &Message) -> Key {
+    if let Some(id) = message.message_id() {
+        return Key::Id(id.to_string());
+    }
+
+    let mut hasher = DefaultHasher::new();
+    message.from().map(ToString::to_string).hash(&mut hasher);
+    message.date().hash(&mut hasher);
+    message.subject().map(ToString::to_string).hash(&mut hasher);
+    // The last message of a mailbox lacks the empty line separating it from the next one
+    message.body.to_string().trim_end().hash(&mut hasher);
+
+    Key::Content(hasher.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INBOX: &str = "From git@z Thu Jan  1 00:00:00 1970
+From: Bob Jones <bob@kernel.org>
+Date: Mon, 03 Jun 2024 09:00:00 +0000
+Subject: only in the inbox
+Message-Id: <inbox@kernel.org>
+
+hello
+
+From git@z Thu Jan  1 00:00:00 1970
+From: Alice Smith <alice@example.com>
+Date: Tue, 04 Jun 2024 09:00:00 +0000
+Subject: no id
+
+body
+";
+
+    const ARCHIVE: &str = "From git@z Thu Jan  1 00:00:00 1970
+From: Bob Jones <bob@kernel.org>
+Date: Mon, 03 Jun 2024 09:00:00 +0000
+Subject: only in the inbox
+Message-Id: <inbox@kernel.org>
+List-Id: <netdev.vger.kernel.org>
+
+hello
+
+From mboxrd@z Thu Jan  1 00:00:00 1970
+From: Alice Smith <alice@example.com>
+Date: Tue, 04 Jun 2024 09:00:00 +0000
+Subject: no id
+X-Received: somewhere else
+
+body
+
+From git@z Thu Jan  1 00:00:00 1970
+From: Alice Smith <alice@example.com>
+Date: Tue, 04 Jun 2024 09:00:00 +0000
+Subject: no id
+
+another body
+";
+
+    #[test]
+    fn merge_mailboxes() {
+        let mailboxes = [
+            ("inbox.mbx", Mailbox::try_from(INBOX).unwrap()),
+            ("archive.mbx", Mailbox::try_from(ARCHIVE).unwrap()),
+        ];
+        let merge = Merge::new(&mailboxes);
+
+        assert_eq!(merge.messages.len(), 3);
+        assert_eq!(merge.dropped.len(), 2);
+
+        let bodies = |sort| {
+            merge
+                .sorted(sort)
+                .iter()
+                .map(|message| message.body.to_string().trim().to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(bodies(SortOrder::Input), ["hello", "body", "another body"]);
+        assert_eq!(bodies(SortOrder::Date), ["hello", "body", "another body"]);
+
+        let mut report = Vec::new();
+        merge.report(&mut report).unwrap();
+        assert_eq!(
+            String::from_utf8(report).unwrap(),
+            "Kept 3 of 5 messages, dropped 2:
+  archive.mbx #1 <inbox@kernel.org> (same Message-Id as inbox.mbx #1)
+  archive.mbx #2 \"no id\" (same content as inbox.mbx #2)
+"
+        );
+    }
+
+    #[test]
+    fn merge_thread() {
+        let input = include_str!("../mailbox/samples/thread.mbx");
+        let mailboxes = [
+            ("a.mbx", Mailbox::try_from(input).unwrap()),
+            ("b.mbx", Mailbox::try_from(input).unwrap()),
+        ];
+        let merge = Merge::new(&mailboxes);
+        assert_eq!(merge.messages, mailboxes[0].1.messages);
+
+        let subjects = merge
+            .sorted(SortOrder::Thread)
+            .iter()
+            .map(|message| message.subject().unwrap().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            subjects,
+            [
+                "[PATCH] mm: drop unused baz",
+                "[PATCH v2 0/2] net: fix foo handling",
+                "[PATCH v2 1/2] net: fix foo",
+                "Re: [PATCH v2 1/2] net: fix foo",
+                "[PATCH v2 2/2] net: add bar helper",
+            ]
+        );
+    }
+}
@@ -0,0 +1,101 @@
+use color_eyre::eyre::{bail, Context, Result};
+use papr::{mailbox, render};
+
+use super::Sink;
+use crate::{
+    cli::{OutputFormat, ShowArgs},
+    config::Config,
+    pager,
+};
+
+/// Writes every message of the mailboxes in the format picked by `--output`
+pub fn run(args: ShowArgs) -> Result<()> {
+    let ShowArgs {
+        common,
+        frontmatter,
+        stat,
+        stat_only,
+        diff,
+    } = args;
+    let mut options = Config::load()?.options(&diff)?;
+
+    if (stat || stat_only) && !matches!(common.output, OutputFormat::Text | OutputFormat::Html) {
+        bail!("--stat and --stat-only only apply to text and HTML output");
+    }
+
+    if frontmatter && common.output == OutputFormat::Mbox {
+        bail!("--frontmatter doesn't apply to mbox output, which keeps messages as they were read");
+    }
+
+    options.diffstat = stat.then(pager::terminal_width);
+
+    let files = super::read_input(&common.files)?;
+    let mut mailboxes = super::select(&common, &files)?;
+
+    if frontmatter {
+        for (_, mailbox) in &mut mailboxes {
+            let messages = std::mem::take(&mut mailbox.messages);
+
+            mailbox.messages = messages
+                .into_iter()
+                .map(|mut message| {
+                    message.body = message.body.front_matter_only();
+                    message
+                })
+                .collect();
+        }
+    }
+
+    super::output(&common, |out, theme, marks| {
+        match Sink::new(out, common.output, theme, marks) {
+            Sink::Spans(mut backend) => {
+                for (path, mailbox) in &mailboxes {
+                    if stat_only {
+                        render::series_stat(
+                            path,
+                            mailbox,
+                            pager::terminal_width(),
+                            backend.as_mut(),
+                        )
+                    } else {
+                        render::mailbox(path, mailbox, &options, backend.as_mut())
+                    }
+                    .with_context(|| "Failed to write to STDOUT")?;
+                }
+
+                backend
+                    .finish()
+                    .with_context(|| "Failed to write to STDOUT")?;
+            }
+            Sink::Raw(mut out) => {
+                match common.output {
+                    OutputFormat::Mbox => mailbox::write_mbox(
+                        &mut out,
+                        mailboxes.iter().flat_map(|(_, mailbox)| &mailbox.messages),
+                    )
+                    .with_context(|| "Failed to write to STDOUT")?,
+                    #[cfg(feature = "json")]
+                    OutputFormat::Json => papr::json::write_document(
+                        &mut out,
+                        mailboxes.iter().map(|(path, mailbox)| (*path, mailbox)),
+                    )
+                    .with_context(|| "Failed to write JSON to STDOUT")?,
+                    #[cfg(feature = "json")]
+                    OutputFormat::Jsonl => {
+                        for (path, mailbox) in &mailboxes {
+                            papr::json::write_lines(&mut out, path, mailbox)
+                                .with_context(|| "Failed to write JSON to STDOUT")?;
+                        }
+                    }
+                    OutputFormat::Text | OutputFormat::Html => {
+                        unreachable!("text and HTML are written as spans")
+                    }
+                }
+
+                out.flush().with_context(|| "Failed to write to STDOUT")?;
+            }
+        }
+
+        Ok(())
+    })
+}
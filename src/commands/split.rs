@@ -0,0 +1,191 @@
+use std::{
+    collections::HashSet,
+    fs,
+    io::{stdout, Write},
+};
+
+use color_eyre::eyre::{Context, Result};
+use papr::mailbox::message::{header::Subject, Message};
+
+use crate::cli::{Naming, SplitArgs};
+
+/// The longest slug of a subject in a file name, like `git format-patch`
+const SLUG_WIDTH: usize = 52;
+
+/// Writes each message picked by the selection to its own file in the output directory
+pub fn run(args: SplitArgs) -> Result<()> {
+    let files = super::read_input(&args.files)?;
+    let mailboxes = super::parse(&files)?;
+
+    fs::create_dir_all(&args.output)
+        .with_context(|| format!("Failed to create {}", args.output.display()))?;
+
+    let messages = mailboxes.iter().flat_map(|(_, mailbox)| {
+        args.selection
+            .indexes(mailbox)
+            .into_iter()
+            .map(|index| &mailbox.messages[index])
+    });
+    let mut names = Names::default();
+    let mut out = stdout().lock();
+
+    for message in messages.filter(|message| !args.patches_only || is_patch(message)) {
+        let path = args.output.join(names.next(message, args.name));
+
+        fs::write(
+            &path,
+            contents(message, args.keep_from_line, args.unescape_from),
+        )
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+
+        writeln!(out, "{}", path.display()).with_context(|| "Failed to write to STDOUT")?;
+    }
+
+    Ok(())
+}
+
+/// Whether the message is a patch of a series rather than its cover letter
+fn is_patch(message: &Message) -> bool {
+    message.is_patch() && !message.is_cover_letter()
+}
+
+/// Names the files in the order they are written, never giving the same name twice
+#[derive(Debug, Default)]
+struct Names {
+    count: usize,
+    taken: HashSet<String>,
+}
+
+impl Names {
+    fn next(&mut self, message: &Message, naming: Naming) -> String {
+        self.count += 1;
+
+        let (stem, extension) = match (naming, message.subject()) {
+            (Naming::Sequence, _) => return format!("{:04}", self.count),
+            (Naming::Subject, Some(subject @ Subject::Patch { index, .. })) => {
+                let number = index.map_or(self.count, |(index, _)| index);
+                (
+                    format!("{:04}-{}", number, slug(&subject.summary())),
+                    "patch",
+                )
+            }
+            (Naming::Subject, subject) => {
+                let summary = subject.map(Subject::summary).unwrap_or_default();
+                (format!("{:04}-{}", self.count, slug(&summary)), "eml")
+            }
+        };
+        let stem = stem.trim_end_matches('-');
+
+        let mut name = format!("{}.{}", stem, extension);
+        let mut copy = 1;
+
+        while !self.taken.insert(name.clone()) {
+            copy += 1;
+            name = format!("{}-{}.{}", stem, copy, extension);
+        }
+
+        name
+    }
+}
+
+/// `text` reduced to letters, digits, `.` and `_` with runs of anything else turned into a `-`
+fn slug(text: &str) -> String {
+    let mut slug = String::new();
+
+    for c in text.chars() {
+        if c.is_ascii_alphanumeric() || c == '.' || c == '_' {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+
+    let slug = slug.chars().take(SLUG_WIDTH).collect::<String>();
+    slug.trim_end_matches(['-', '.']).to_string()
+}
+
+/// The message as it is written to its file: without its `From ` line unless `keep_from_line`,
+/// and with the `>From ` lines unescaped when `unescape_from`
+fn contents(message: &Message, keep_from_line: bool, unescape_from: bool) -> String {
+    let mut raw = message.raw;
+
+    if !keep_from_line && raw.starts_with("From ") {
+        raw = raw.split_once('\n').map_or("", |(_, rest)| rest);
+    }
+
+    let mut contents = String::new();
+
+    for line in raw.trim_end_matches('\n').split('\n') {
+        let unquoted = line.trim_start_matches('>');
+
+        if unescape_from && unquoted.len() < line.len() && unquoted.starts_with("From ") {
+            contents.push_str(&line[1..]);
+        } else {
+            contents.push_str(line);
+        }
+
+        contents.push('\n');
+    }
+
+    contents
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use papr::mailbox::Mailbox;
+
+    #[test]
+    fn name_files() {
+        let input = include_str!("../mailbox/samples/thread.mbx");
+        let mailbox = Mailbox::try_from(input).unwrap();
+        let named = |naming| {
+            let mut names = Names::default();
+            mailbox
+                .messages
+                .iter()
+                .map(|message| names.next(message, naming))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            named(Naming::Sequence),
+            ["0001", "0002", "0003", "0004", "0005"]
+        );
+        assert_eq!(
+            named(Naming::Subject),
+            [
+                "0000-net-fix-foo-handling.patch",
+                "0001-net-fix-foo.patch",
+                "0002-net-add-bar-helper.patch",
+                "0004-Re-PATCH-v2-1-2-net-fix-foo.eml",
+                "0005-mm-drop-unused-baz.patch",
+            ]
+        );
+    }
+
+    #[test]
+    fn slug_subjects() {
+        assert_eq!(slug("net: fix foo (again)"), "net-fix-foo-again");
+        assert_eq!(slug("  bump to v1.2."), "bump-to-v1.2");
+        assert_eq!(
+            slug(&"abc ".repeat(20)),
+            "abc-".repeat(13).trim_end_matches('-')
+        );
+    }
+
+    #[test]
+    fn message_contents() {
+        let input = "From git@z Thu Jan  1 00:00:00 1970\nSubject: foo\n\n>From the start\n>>From here\n> From there\n\n";
+        let message = Message::try_from(input).unwrap();
+
+        assert_eq!(
+            contents(&message, false, true),
+            "Subject: foo\n\nFrom the start\n>From here\n> From there\n"
+        );
+        assert_eq!(
+            contents(&message, true, false),
+            input.trim_end().to_string() + "\n"
+        );
+    }
+}
@@ -0,0 +1,206 @@
+use std::collections::HashMap;
+
+use color_eyre::eyre::{bail, Context, Result};
+use papr::{
+    mailbox::{message::Message, thread::Threads},
+    render::{self, Role, Span},
+};
+
+use super::Sink;
+use crate::cli::Common;
+
+/// Writes how many messages, threads, patches and series the mailboxes have, and who wrote them
+pub fn run(common: Common) -> Result<()> {
+    let files = super::read_input(&common.files)?;
+    let messages = super::select(&common, &files)?
+        .into_iter()
+        .flat_map(|(_, mailbox)| mailbox.messages)
+        .collect::<Vec<_>>();
+    let stats = Stats::new(&messages);
+
+    super::output(&common, |out, theme, marks| {
+        match Sink::new(out, common.output, theme, marks) {
+            Sink::Spans(mut backend) => {
+                for line in stats.lines(&messages) {
+                    backend
+                        .line(&line)
+                        .with_context(|| "Failed to write to STDOUT")?;
+                }
+
+                backend
+                    .finish()
+                    .with_context(|| "Failed to write to STDOUT")
+            }
+            #[cfg(feature = "json")]
+            Sink::Raw(mut out) if common.output == crate::cli::OutputFormat::Json => {
+                serde_json::to_writer_pretty(&mut out, &stats)
+                    .with_context(|| "Failed to write JSON to STDOUT")?;
+                writeln!(out).with_context(|| "Failed to write to STDOUT")
+            }
+            Sink::Raw(_) => bail!("`papr stats` only writes text, HTML or JSON"),
+        }
+    })
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(serde::Serialize))]
+struct Stats {
+    messages: usize,
+    threads: usize,
+    /// Threads with more than one patch
+    series: usize,
+    patches: usize,
+    cover_letters: usize,
+    replies: usize,
+    /// The oldest and newest dates, in RFC 3339
+    first: Option<String>,
+    last: Option<String>,
+    /// Authors with the most messages first
+    authors: Vec<Author>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(serde::Serialize))]
+struct Author {
+    email: String,
+    messages: usize,
+    /// The index of the first message of the author, to render its `From`
+    #[cfg_attr(feature = "json", serde(skip))]
+    first: usize,
+}
+
+impl Stats {
+    fn new(messages: &[Message]) -> Self {
+        let threads = Threads::new(messages);
+        let mut stats = Stats {
+            messages: messages.len(),
+            threads: threads.roots().len(),
+            series: threads
+                .roots()
+                .iter()
+                .filter_map(|root| threads.series(messages, *root))
+                .filter(|series| series.total > 1)
+                .count(),
+            ..Default::default()
+        };
+
+        let mut authors = HashMap::new();
+
+        for (index, message) in messages.iter().enumerate() {
+            if message.is_cover_letter() {
+                stats.cover_letters += 1;
+            } else if message.is_patch() {
+                stats.patches += 1;
+            } else if message.is_reply() {
+                stats.replies += 1;
+            }
+
+            if let Some(from) = message.from() {
+                authors
+                    .entry(from.email.to_string())
+                    .or_insert((0, index))
+                    .0 += 1;
+            }
+        }
+
+        let dates = messages.iter().filter_map(Message::date);
+        stats.first = dates.clone().min().map(|date| date.to_rfc3339());
+        stats.last = dates.max().map(|date| date.to_rfc3339());
+
+        stats.authors = authors
+            .into_iter()
+            .map(|(email, (messages, first))| Author {
+                email,
+                messages,
+                first,
+            })
+            .collect();
+        stats.authors.sort_by(|a, b| {
+            b.messages
+                .cmp(&a.messages)
+                .then_with(|| a.email.cmp(&b.email))
+        });
+
+        stats
+    }
+
+    fn lines<'a>(&'a self, messages: &'a [Message]) -> Vec<Vec<Span<'a>>> {
+        let label =
+            |name: &str| Span::new(Role::HeaderKey, format!("{:<15}", format!("{}:", name)));
+        let field = |name: &str, value: usize| vec![label(name), Span::text(value.to_string())];
+
+        let mut lines = vec![
+            field("messages", self.messages),
+            field("threads", self.threads),
+            field("series", self.series),
+            field("patches", self.patches),
+            field("cover letters", self.cover_letters),
+            field("replies", self.replies),
+        ];
+
+        if let (Some(first), Some(last)) = (&self.first, &self.last) {
+            lines.push(vec![
+                label("dates"),
+                Span::new(Role::Date, first.as_str()),
+                Span::text(" to "),
+                Span::new(Role::Date, last.as_str()),
+            ]);
+        }
+
+        lines.push(field("authors", self.authors.len()));
+
+        let width = self
+            .authors
+            .first()
+            .map_or(0, |a| a.messages.to_string().len());
+
+        for author in &self.authors {
+            let mut line = vec![Span::text(format!("  {:>1$} ", author.messages, width))];
+
+            match messages[author.first].from() {
+                Some(from) => line.extend(render::person_spans(from)),
+                None => line.push(Span::new(Role::Email, author.email.as_str())),
+            }
+
+            lines.push(line);
+        }
+
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use papr::mailbox::Mailbox;
+
+    #[test]
+    fn stats_thread() {
+        let input = include_str!("../mailbox/samples/thread.mbx");
+        let mailbox = Mailbox::try_from(input).unwrap();
+        let stats = Stats::new(&mailbox.messages);
+
+        assert_eq!(stats.messages, 5);
+        assert_eq!(stats.threads, 2);
+        assert_eq!(stats.series, 1);
+        assert_eq!(stats.patches, 3);
+        assert_eq!(stats.cover_letters, 1);
+        assert_eq!(stats.replies, 1);
+        assert_eq!(stats.first.as_deref(), Some("2024-06-03T10:00:00+00:00"));
+        assert_eq!(stats.last.as_deref(), Some("2024-06-05T14:00:00+00:00"));
+
+        let authors = stats
+            .authors
+            .iter()
+            .map(|author| (author.email.as_str(), author.messages))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            authors,
+            [
+                ("alice@example.com", 3),
+                ("bob@kernel.org", 1),
+                ("carol@corp.example.org", 1)
+            ]
+        );
+    }
+}
@@ -0,0 +1,103 @@
+use color_eyre::eyre::{Context, Result};
+use papr::{
+    mailbox::{message::Message, thread::Threads},
+    render::{self, Backend, Role, Span},
+};
+
+use super::Sink;
+use crate::cli::Common;
+
+/// Writes the threads of the mailboxes as trees, one line per message. Messages of every
+/// mailbox are threaded together, so replies saved in another file still find their parent
+pub fn run(common: Common) -> Result<()> {
+    let files = super::read_input(&common.files)?;
+    let messages = super::select(&common, &files)?
+        .into_iter()
+        .flat_map(|(_, mailbox)| mailbox.messages)
+        .collect::<Vec<_>>();
+    let threads = Threads::new(&messages);
+
+    super::output(&common, |out, theme, marks| {
+        let mut backend = Sink::new(out, common.output, theme, marks).spans("thread")?;
+
+        let tree = Tree {
+            messages: &messages,
+            threads: &threads,
+            width: messages.len().to_string().len(),
+        };
+
+        for root in threads.roots() {
+            backend.begin_message(*root, &messages[*root])?;
+            tree.write(backend.as_mut(), *root, "", None)
+                .with_context(|| "Failed to write to STDOUT")?;
+            backend.end_message()?;
+        }
+
+        backend
+            .finish()
+            .with_context(|| "Failed to write to STDOUT")
+    })
+}
+
+struct Tree<'a, 'input> {
+    messages: &'a [Message<'input>],
+    threads: &'a Threads,
+    /// The width of the largest message number
+    width: usize,
+}
+
+impl Tree<'_, '_> {
+    /// Writes the line of the message `index` and then its replies. `indent` is drawn before the
+    /// branch of the message, `last` tells whether it is the last reply to its parent and is
+    /// `None` for the root of the thread, which has no branch
+    fn write(
+        &self,
+        backend: &mut dyn Backend,
+        index: usize,
+        indent: &str,
+        last: Option<bool>,
+    ) -> std::io::Result<()> {
+        let message = &self.messages[index];
+        let mut line = vec![
+            Span::new(Role::Separator, format!("{:>1$}", index + 1, self.width)),
+            Span::text(" "),
+        ];
+
+        if let Some(date) = message.date() {
+            line.push(Span::new(Role::Date, date.format("%Y-%m-%d").to_string()));
+            line.push(Span::text(" "));
+        }
+
+        let (branch, continuation) = match last {
+            None => ("", ""),
+            Some(true) => ("└─ ", "   "),
+            Some(false) => ("├─ ", "│  "),
+        };
+
+        line.push(Span::new(Role::Separator, format!("{}{}", indent, branch)));
+
+        match message.subject() {
+            Some(subject) => line.extend(render::subject_spans(subject)),
+            None => line.push(Span::text("(no subject)")),
+        }
+
+        if let Some(from) = message.from() {
+            line.push(Span::text("  "));
+            line.push(match from.name {
+                Some(name) => Span::new(Role::PersonName, name),
+                None => Span::new(Role::Email, from.email.to_string()),
+            });
+        }
+
+        backend.line(&line)?;
+
+        let replies = self.threads.children(index);
+        let indent = format!("{}{}", indent, continuation);
+
+        for (i, reply) in replies.iter().enumerate() {
+            self.write(backend, *reply, &indent, Some(i + 1 == replies.len()))?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,208 @@
+use std::{collections::HashMap, env, path::PathBuf};
+
+use clap::ValueEnum;
+use color_eyre::eyre::{eyre, Context, Result};
+use papr::{
+    diff::words::Tokenizer,
+    render::{style::Style, theme::Theme, Options, Role},
+    whitespace::Rules,
+};
+use regex::Regex;
+
+use crate::{
+    cli::{DiffArgs, WordDiff},
+    pager,
+};
+
+/// The contents of papr's configuration file
+///
+/// ```toml
+/// # One of the built-in themes: dark (default), light or monochrome
+/// theme = "light"
+///
+/// # The whitespace errors highlighted in added lines, like git's `core.whitespace`
+/// whitespace = "-crlf,-missing-newline"
+///
+/// # How changed lines are compared: word (default), char or none, or by a regex
+/// word_diff = "char"
+/// word_diff_regex = "[a-zA-Z_]+|[0-9]+"
+///
+/// # Whether the code in diffs is syntax highlighted, true by default
+/// syntax = false
+///
+/// # Whether hunks are shown in two columns, old and new lines side by side
+/// side_by_side = true
+///
+/// # Whether the lines of hunks start with their old and new line numbers
+/// line_numbers = true
+///
+/// # Overrides for single roles, written like git colors
+/// [styles]
+/// email = "italic #5f87af"
+/// patch_index = "bold 208"
+/// ```
+#[derive(Debug, Default)]
+pub struct Config {
+    pub theme: Option<String>,
+    pub whitespace: Option<String>,
+    pub word_diff: Option<String>,
+    pub word_diff_regex: Option<String>,
+    pub syntax: Option<bool>,
+    pub side_by_side: Option<bool>,
+    pub line_numbers: Option<bool>,
+    pub styles: HashMap<String, String>,
+}
+
+impl Config {
+    /// Where the configuration file is looked for: `$PAPR_CONFIG`, then
+    /// `$XDG_CONFIG_HOME/papr/config.toml` and finally `~/.config/papr/config.toml`
+    pub fn path() -> Option<PathBuf> {
+        if let Some(path) = env::var_os("PAPR_CONFIG") {
+            return Some(PathBuf::from(path));
+        }
+
+        let config_home = env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+
+        Some(config_home.join("papr").join("config.toml"))
+    }
+
+    /// Loads the configuration file, a missing file results in the default configuration
+    pub fn load() -> Result<Config> {
+        let Some(path) = Config::path().filter(|path| path.exists()) else {
+            return Ok(Config::default());
+        };
+
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Config file {} could not be opened", path.display()))?;
+
+        Config::parse(&content)
+            .with_context(|| format!("Parsing config file {}", path.display()))
+    }
+
+    /// Reads the configuration from the contents of a file, unknown keys are errors
+    fn parse(content: &str) -> Result<Config> {
+        let table: toml::Table = content.parse()?;
+        let mut config = Config::default();
+
+        for (key, value) in table {
+            let string = || match &value {
+                toml::Value::String(string) => Ok(Some(string.clone())),
+                _ => Err(eyre!("`{}` should be a string", key)),
+            };
+            let boolean = || match &value {
+                toml::Value::Boolean(boolean) => Ok(Some(*boolean)),
+                _ => Err(eyre!("`{}` should be true or false", key)),
+            };
+
+            match key.as_str() {
+                "theme" => config.theme = string()?,
+                "whitespace" => config.whitespace = string()?,
+                "word_diff" => config.word_diff = string()?,
+                "word_diff_regex" => config.word_diff_regex = string()?,
+                "syntax" => config.syntax = boolean()?,
+                "side_by_side" => config.side_by_side = boolean()?,
+                "line_numbers" => config.line_numbers = boolean()?,
+                "styles" => {
+                    let toml::Value::Table(styles) = &value else {
+                        return Err(eyre!("`styles` should be a table"));
+                    };
+
+                    for (role, style) in styles {
+                        let toml::Value::String(style) = style else {
+                            return Err(eyre!("The style of `{}` should be a string", role));
+                        };
+
+                        config.styles.insert(role.clone(), style.clone());
+                    }
+                }
+                _ => return Err(eyre!("Unknown key `{}`", key)),
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// The whitespace rules to check, `rules` take precedence over the configured rules which
+    /// take precedence over every rule
+    pub fn whitespace(&self, rules: Option<Rules>) -> Result<Rules> {
+        match (rules, &self.whitespace) {
+            (Some(rules), _) => Ok(rules),
+            (None, Some(rules)) => rules
+                .parse()
+                .with_context(|| "Parsing `whitespace` in the config file"),
+            (None, None) => Ok(Rules::default()),
+        }
+    }
+
+    /// The options to render diffs with, those of `args` take precedence over the configured
+    /// ones
+    pub fn options(&self, args: &DiffArgs) -> Result<Options> {
+        Ok(Options {
+            whitespace: self.whitespace(args.whitespace)?,
+            word_diff: self.word_diff(args.word_diff, args.word_diff_regex.clone())?,
+            syntax: !args.no_syntax && self.syntax.unwrap_or(true),
+            side_by_side: (args.side_by_side || self.side_by_side.unwrap_or(false))
+                .then(pager::terminal_width),
+            line_numbers: args.line_numbers || self.line_numbers.unwrap_or(false),
+            diffstat: None,
+            binary: args.binary,
+        })
+    }
+
+    /// How changed lines are compared, a regex takes precedence over a mode and the command
+    /// line over the config file. Lines are compared by words by default
+    fn word_diff(&self, mode: Option<WordDiff>, regex: Option<Regex>) -> Result<Option<Tokenizer>> {
+        if let Some(regex) = regex {
+            return Ok(Some(Tokenizer::Regex(regex)));
+        }
+
+        let mode = match (mode, &self.word_diff_regex, &self.word_diff) {
+            (Some(mode), _, _) => mode,
+            (None, Some(regex), _) => {
+                let regex = Regex::new(regex)
+                    .with_context(|| "Parsing `word_diff_regex` in the config file")?;
+                return Ok(Some(Tokenizer::Regex(regex)));
+            }
+            (None, None, Some(mode)) => WordDiff::from_str(mode, false).map_err(|_| {
+                eyre!(
+                    "Unknown `word_diff` mode `{}` in the config file, expected word, char or none",
+                    mode
+                )
+            })?,
+            (None, None, None) => WordDiff::Word,
+        };
+
+        Ok(match mode {
+            WordDiff::Word => Some(Tokenizer::Word),
+            WordDiff::Char => Some(Tokenizer::Char),
+            WordDiff::None => None,
+        })
+    }
+
+    /// Builds the theme to render with, `name` takes precedence over the configured theme which
+    /// takes precedence over `default`
+    pub fn theme(&self, name: Option<&str>, default: &str) -> Result<Theme> {
+        let name = name.or(self.theme.as_deref()).unwrap_or(default);
+        let mut theme = Theme::builtin(name).ok_or(eyre!(
+            "Unknown theme `{}`. Available themes are: {}",
+            name,
+            Theme::BUILTIN.join(", ")
+        ))?;
+
+        for (role, style) in &self.styles {
+            let role: Role = role
+                .parse()
+                .with_context(|| "In the `[styles]` section of the config file")?;
+            let style: Style = style
+                .parse()
+                .with_context(|| format!("Parsing the style of `{}`", role.name()))?;
+
+            theme.set(role, style);
+        }
+
+        Ok(theme)
+    }
+}
@@ -0,0 +1,562 @@
+//! A model of the part of a patch after its `---` line: the diffstat, the diff of each file split
+//! in hunks, and what follows them like the signature
+
+pub mod stat;
+pub mod words;
+
+/// The part of a patch after its `---` line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Patch<'a> {
+    /// The lines before the first file, like the diffstat
+    pub preamble: Vec<&'a str>,
+    pub files: Vec<File<'a>>,
+    /// The lines after the last hunk, like the `-- ` signature
+    pub rest: Vec<&'a str>,
+}
+
+/// The diff of a single file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct File<'a> {
+    /// The `diff --git` line and the lines up to the first hunk, like `index` and `---`/`+++`
+    pub header: Vec<&'a str>,
+    pub hunks: Vec<Hunk<'a>>,
+    /// The `GIT binary patch` replacing the hunks of binary files
+    pub binary: Option<Binary<'a>>,
+}
+
+/// A change to a file besides its content, from the extended header lines of git
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change<'a> {
+    /// `rename from`/`rename to`, with the `similarity index` in percents
+    Renamed {
+        from: &'a str,
+        to: &'a str,
+        similarity: Option<u8>,
+    },
+    /// `copy from`/`copy to`, with the `similarity index` in percents
+    Copied {
+        from: &'a str,
+        to: &'a str,
+        similarity: Option<u8>,
+    },
+    /// `new file mode`
+    Created { mode: &'a str },
+    /// `deleted file mode`
+    Deleted { mode: &'a str },
+    /// `old mode`/`new mode`
+    Mode { old: &'a str, new: &'a str },
+}
+
+/// A `GIT binary patch` block
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Binary<'a> {
+    /// The lines of the block, from `GIT binary patch` to the empty line closing its payloads
+    pub lines: Vec<&'a str>,
+    /// The payload turning the old file into the new one, then the one reverting it
+    pub payloads: Vec<Payload>,
+}
+
+/// The base85 encoded data of a binary patch, with the size of the data once inflated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Payload {
+    /// The whole content of the file
+    Literal(usize),
+    /// The changes from the other version of the file
+    Delta(usize),
+}
+
+/// A `@@ -a,b +c,d @@` line and the lines it covers, or a `@@@ -a,b -c,d +e,f @@@` line of the
+/// combined diff of a merge
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hunk<'a> {
+    pub header: &'a str,
+    /// The first line and the number of lines of the hunk in the old file, the first parent of a
+    /// combined diff. `None` when the header can't be parsed
+    pub old: Option<(usize, usize)>,
+    /// The first line and the number of lines of the hunk in the new file
+    pub new: Option<(usize, usize)>,
+    /// The ranges of the other parents of a combined diff, empty for other diffs
+    pub other_parents: Vec<(usize, usize)>,
+    /// The number of characters prefixing the lines: one, or one per parent for combined diffs
+    pub columns: usize,
+    pub lines: Vec<Line<'a>>,
+}
+
+/// A line of a hunk. Lines keep the `\r` of CRLF line endings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Line<'a> {
+    pub kind: Kind,
+    /// The line as it is in the patch, with its prefix
+    pub raw: &'a str,
+    /// The number of characters of its prefix, one per parent for combined diffs
+    pub columns: usize,
+}
+
+/// What a line is to the new file. In combined diffs a line is added when it is new to a parent
+/// and removed when it is missing from the result
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Context,
+    Added,
+    Removed,
+    /// The `\ No newline at end of file` following the last line of a file
+    NoNewline,
+}
+
+impl<'a> Patch<'a> {
+    /// Parses the part of a patch after its `---` line. Anything can be parsed: lines that don't
+    /// belong to a diff end up in the preamble or the rest
+    pub fn parse(text: &'a str) -> Self {
+        // Unlike `str::lines` this keeps the `\r` of CRLF line endings, to tell them apart
+        let mut lines = text
+            .split_inclusive('\n')
+            .map(|line| line.strip_suffix('\n').unwrap_or(line))
+            .peekable();
+        let mut patch = Patch {
+            preamble: Vec::new(),
+            files: Vec::new(),
+            rest: Vec::new(),
+        };
+
+        while let Some(line) = lines.next_if(|line| !is_file_start(line) && !is_signature(line)) {
+            patch.preamble.push(line);
+        }
+
+        while let Some(line) = lines.next_if(|line| is_file_start(line)) {
+            let mut file = File {
+                header: vec![line],
+                hunks: Vec::new(),
+                binary: None,
+            };
+
+            while let Some(line) = lines.next_if(|line| {
+                !line.starts_with("@@")
+                    && !is_file_start(line)
+                    && !is_signature(line)
+                    && !is_binary_start(line)
+            }) {
+                file.header.push(line);
+            }
+
+            if let Some(line) = lines.next_if(|line| is_binary_start(line)) {
+                let mut binary = Binary {
+                    lines: vec![line],
+                    payloads: Vec::new(),
+                };
+
+                // Each payload is a `literal <size>` or `delta <size>` line, its data and an
+                // empty line
+                while let Some((line, payload)) = lines
+                    .peek()
+                    .and_then(|line| Some((*line, Payload::parse(line)?)))
+                {
+                    lines.next();
+                    binary.lines.push(line);
+                    binary.payloads.push(payload);
+
+                    while let Some(line) = lines.next_if(|line| {
+                        !line.trim_end_matches('\r').is_empty()
+                            && !is_file_start(line)
+                            && !is_signature(line)
+                    }) {
+                        binary.lines.push(line);
+                    }
+
+                    if let Some(line) = lines.next_if(|line| line.trim_end_matches('\r').is_empty())
+                    {
+                        binary.lines.push(line);
+                    }
+                }
+
+                file.binary = Some(binary);
+            }
+
+            while let Some(header) = lines.next_if(|line| line.starts_with("@@")) {
+                let mut hunk = Hunk::new(header);
+                let columns = hunk.columns;
+                let counted = hunk.old.is_some() && hunk.new.is_some();
+                // The lines left in each parent and in the result
+                let (mut old, mut new) = match (hunk.old, hunk.new) {
+                    (Some((_, old)), Some((_, new))) => (
+                        std::iter::once(old)
+                            .chain(hunk.other_parents.iter().map(|(_, count)| *count))
+                            .collect(),
+                        new,
+                    ),
+                    _ => (vec![usize::MAX; columns], usize::MAX),
+                };
+
+                // The lines are counted as in the header, so that a removed `-- ` isn't taken
+                // for a signature, and the `\` lines are taken whatever the count
+                while let Some(line) = lines.peek() {
+                    if line.starts_with('\\') {
+                        hunk.lines.push(Line {
+                            kind: Kind::NoNewline,
+                            raw: line,
+                            columns,
+                        });
+                        lines.next();
+                        continue;
+                    }
+
+                    if new == 0 && old.iter().all(|old| *old == 0) {
+                        break;
+                    }
+
+                    // Some mailers strip the space of empty context lines
+                    let prefix = match line.get(..columns) {
+                        _ if line.is_empty() => " ".repeat(columns),
+                        Some(prefix) if prefix.chars().all(|c| " +-".contains(c)) => {
+                            prefix.to_string()
+                        }
+                        _ => break,
+                    };
+                    let kind = if prefix.contains('-') {
+                        Kind::Removed
+                    } else if prefix.contains('+') {
+                        Kind::Added
+                    } else {
+                        Kind::Context
+                    };
+
+                    // A line removed from the result is in the parents marked by a `-`, the
+                    // other lines are in the parents marked by a space
+                    let parent = if kind == Kind::Removed { '-' } else { ' ' };
+                    let parents = prefix
+                        .chars()
+                        .enumerate()
+                        .filter(|(_, c)| *c == parent)
+                        .map(|(index, _)| index)
+                        .collect::<Vec<_>>();
+
+                    if kind != Kind::Removed && new == 0
+                        || parents.iter().any(|parent| old[*parent] == 0)
+                        || kind == Kind::Removed && !counted && is_signature(line)
+                    {
+                        break;
+                    }
+
+                    if kind != Kind::Removed {
+                        new -= 1;
+                    }
+
+                    for parent in parents {
+                        old[parent] -= 1;
+                    }
+
+                    hunk.lines.push(Line {
+                        kind,
+                        raw: line,
+                        columns,
+                    });
+                    lines.next();
+                }
+
+                file.hunks.push(hunk);
+            }
+
+            patch.files.push(file);
+        }
+
+        patch.rest.extend(lines);
+        patch
+    }
+}
+
+impl<'a> File<'a> {
+    /// The path of the file after the patch, or before it when the patch deletes the file. It is
+    /// taken from the `+++`/`---` lines without their `a/` and `b/`, or else the `diff` line
+    pub fn path(&self) -> Option<&'a str> {
+        let path = |marker: &str| {
+            self.header
+                .iter()
+                .find_map(|line| line.strip_prefix(marker))
+                // Timestamps follow the path after a tab in the output of `diff -u`
+                .map(|path| path.split('\t').next().unwrap_or(path).trim_end())
+                .filter(|path| *path != "/dev/null")
+        };
+
+        if let Some(path) = path("+++ ").or_else(|| path("--- ")) {
+            return Some(
+                path.strip_prefix("b/")
+                    .or_else(|| path.strip_prefix("a/"))
+                    .unwrap_or(path),
+            );
+        }
+
+        let paths = self.header.first()?.strip_prefix("diff --git ")?;
+
+        paths.rsplit_once(" b/").map(|(_, path)| path.trim_end())
+    }
+
+    /// The renames, copies and mode changes told by the extended header lines
+    pub fn changes(&self) -> Vec<Change<'a>> {
+        let value = |key: &str| {
+            self.header
+                .iter()
+                .find_map(|line| line.trim_end_matches('\r').strip_prefix(key))
+        };
+        let similarity = value("similarity index ")
+            .and_then(|similarity| similarity.trim_end_matches('%').parse().ok());
+        let mut changes = Vec::new();
+
+        if let (Some(from), Some(to)) = (value("rename from "), value("rename to ")) {
+            changes.push(Change::Renamed {
+                from,
+                to,
+                similarity,
+            });
+        }
+
+        if let (Some(from), Some(to)) = (value("copy from "), value("copy to ")) {
+            changes.push(Change::Copied {
+                from,
+                to,
+                similarity,
+            });
+        }
+
+        if let Some(mode) = value("new file mode ") {
+            changes.push(Change::Created { mode });
+        }
+
+        if let Some(mode) = value("deleted file mode ") {
+            changes.push(Change::Deleted { mode });
+        }
+
+        if let (Some(old), Some(new)) = (value("old mode "), value("new mode ")) {
+            changes.push(Change::Mode { old, new });
+        }
+
+        changes
+    }
+}
+
+impl Payload {
+    /// Parses the `literal <size>` or `delta <size>` line starting a payload
+    fn parse(line: &str) -> Option<Payload> {
+        let (kind, size) = line.trim_end_matches('\r').split_once(' ')?;
+        let size = size.parse().ok()?;
+
+        match kind {
+            "literal" => Some(Payload::Literal(size)),
+            "delta" => Some(Payload::Delta(size)),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> Hunk<'a> {
+    /// A hunk without lines yet, from its `@@ -a,b +c,d @@` header
+    fn new(header: &'a str) -> Self {
+        let ranges = header.trim_start_matches('@');
+        // A combined diff of N parents starts its hunks with N + 1 `@`
+        let columns = (header.len() - ranges.len()).saturating_sub(1).max(1);
+        let mut ranges = ranges.split_whitespace();
+        let parents = (0..columns)
+            .map(|_| ranges.next().and_then(|range| parse_range(range, '-')))
+            .collect::<Option<Vec<_>>>();
+        let new = ranges.next().and_then(|range| parse_range(range, '+'));
+
+        Hunk {
+            header,
+            old: parents.as_ref().map(|parents| parents[0]),
+            new,
+            other_parents: parents
+                .map(|parents| parents[1..].to_vec())
+                .unwrap_or_default(),
+            columns,
+            lines: Vec::new(),
+        }
+    }
+}
+
+impl<'a> Line<'a> {
+    /// The line without its prefix and its `\r`
+    pub fn content(&self) -> &'a str {
+        let content = match self.kind {
+            Kind::NoNewline => self.raw,
+            _ => self.raw.get(self.columns..).unwrap_or(""),
+        };
+
+        content.strip_suffix('\r').unwrap_or(content)
+    }
+}
+
+/// Parses the `-a,b` or `+c,d` range of a hunk header, `b` and `d` are 1 when omitted
+fn parse_range(range: &str, sign: char) -> Option<(usize, usize)> {
+    let range = range.strip_prefix(sign)?;
+    let (start, count) = range.split_once(',').unwrap_or((range, "1"));
+
+    Some((start.parse().ok()?, count.parse().ok()?))
+}
+
+fn is_file_start(line: &str) -> bool {
+    line.starts_with("diff ")
+}
+
+fn is_binary_start(line: &str) -> bool {
+    line.trim_end_matches('\r') == "GIT binary patch"
+}
+
+/// Whether the line is one of git's extended header lines summarized by [`File::changes`]
+pub fn is_extended_header(line: &str) -> bool {
+    [
+        "old mode ",
+        "new mode ",
+        "deleted file mode ",
+        "new file mode ",
+        "copy from ",
+        "copy to ",
+        "rename from ",
+        "rename to ",
+        "similarity index ",
+    ]
+    .iter()
+    .any(|key| line.starts_with(key))
+}
+
+/// Whether the line starts the signature closing a message
+pub fn is_signature(line: &str) -> bool {
+    matches!(line.trim_end_matches('\r'), "--" | "-- ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_patch() {
+        let text = " foo.c | 3 ++-\n\ndiff --git a/foo.c b/foo.c\nindex 1234..5678 100644\n--- a/foo.c\n+++ b/foo.c\n@@ -1,3 +1,4 @@ int main()\n int foo;\n--- a comment\n+int bar;\n+int baz;\n\n@@ -10 +11 @@\n-x\n+y\n\\ No newline at end of file\n-- \n2.43.0\n";
+        let patch = Patch::parse(text);
+
+        assert_eq!(patch.preamble, [" foo.c | 3 ++-", ""]);
+        assert_eq!(patch.rest, ["-- ", "2.43.0"]);
+        assert_eq!(patch.files.len(), 1);
+
+        let file = &patch.files[0];
+        assert_eq!(file.header.len(), 4);
+        assert_eq!(file.path(), Some("foo.c"));
+        assert_eq!(file.hunks.len(), 2);
+
+        let hunk = &file.hunks[0];
+        assert_eq!((hunk.old, hunk.new), (Some((1, 3)), Some((1, 4))));
+        assert_eq!(
+            hunk.lines
+                .iter()
+                .map(|line| (line.kind, line.content()))
+                .collect::<Vec<_>>(),
+            [
+                (Kind::Context, "int foo;"),
+                (Kind::Removed, "-- a comment"),
+                (Kind::Added, "int bar;"),
+                (Kind::Added, "int baz;"),
+                (Kind::Context, ""),
+            ]
+        );
+
+        let hunk = &file.hunks[1];
+        assert_eq!((hunk.old, hunk.new), (Some((10, 1)), Some((11, 1))));
+        assert_eq!(hunk.lines.len(), 3);
+        assert_eq!(hunk.lines[2].kind, Kind::NoNewline);
+    }
+
+    #[test]
+    fn file_paths() {
+        let path = |text| Patch::parse(text).files[0].path();
+
+        assert_eq!(
+            path("diff --git a/old.c b/old.c\n--- a/old.c\n+++ /dev/null\n"),
+            Some("old.c")
+        );
+        assert_eq!(
+            path("diff -u foo.h.orig foo.h\n--- foo.h.orig\t2024-01-01\n+++ foo.h\t2024-01-02\n"),
+            Some("foo.h")
+        );
+        assert_eq!(
+            path("diff --git a/lib/a.rs b/lib/b.rs\nsimilarity index 100%\n"),
+            Some("lib/b.rs")
+        );
+    }
+
+    #[test]
+    fn parse_extended_headers() {
+        let text = "diff --git a/a.c b/b.c\nold mode 100644\nnew mode 100755\nsimilarity index 95%\nrename from a.c\nrename to b.c\n@@ -1 +1 @@\n-a\n+b\ndiff --git a/logo.png b/logo.png\nnew file mode 100644\nindex 0000000..1234567\nGIT binary patch\nliteral 4300\nzcmV;fV|5gdJ+\nzcmV;fV|5gdJ+\n\nliteral 0\nHcmV?d00001\n\n-- \n2.43.0\n";
+        let patch = Patch::parse(text);
+
+        assert_eq!(patch.files.len(), 2);
+        assert_eq!(patch.rest, ["-- ", "2.43.0"]);
+        assert_eq!(
+            patch.files[0].changes(),
+            [
+                Change::Renamed {
+                    from: "a.c",
+                    to: "b.c",
+                    similarity: Some(95)
+                },
+                Change::Mode {
+                    old: "100644",
+                    new: "100755"
+                },
+            ]
+        );
+        assert_eq!(patch.files[0].hunks.len(), 1);
+
+        let file = &patch.files[1];
+        let binary = file.binary.as_ref().unwrap();
+
+        assert_eq!(file.changes(), [Change::Created { mode: "100644" }]);
+        assert_eq!(file.header.len(), 3);
+        assert_eq!(binary.lines.len(), 8);
+        assert_eq!(
+            binary.payloads,
+            [Payload::Literal(4300), Payload::Literal(0)]
+        );
+    }
+
+    #[test]
+    fn parse_combined_diff() {
+        let text = "diff --cc f.c\nindex 1234567,89abcde..0123456\n--- a/f.c\n+++ b/f.c\n@@@ -1,4 -1,3 +1,4 @@@\n  a\n- b\n -c\n++d\n +f\n  e\n-- \n2.43.0\n";
+        let patch = Patch::parse(text);
+        let hunk = &patch.files[0].hunks[0];
+
+        assert_eq!(patch.files[0].path(), Some("f.c"));
+        assert_eq!(hunk.columns, 2);
+        assert_eq!((hunk.old, hunk.new), (Some((1, 4)), Some((1, 4))));
+        assert_eq!(hunk.other_parents, [(1, 3)]);
+        assert_eq!(
+            hunk.lines
+                .iter()
+                .map(|line| (line.kind, line.content()))
+                .collect::<Vec<_>>(),
+            [
+                (Kind::Context, "a"),
+                (Kind::Removed, "b"),
+                (Kind::Removed, "c"),
+                (Kind::Added, "d"),
+                (Kind::Added, "f"),
+                (Kind::Context, "e"),
+            ]
+        );
+        assert_eq!(patch.rest, ["-- ", "2.43.0"]);
+    }
+
+    #[test]
+    fn parse_without_diff() {
+        let patch = Patch::parse(" foo.c | 1 +\n 1 file changed\n--\n2.43.0\n");
+
+        assert_eq!(patch.preamble.len(), 2);
+        assert!(patch.files.is_empty());
+        assert_eq!(patch.rest, ["--", "2.43.0"]);
+    }
+
+    #[test]
+    fn parse_broken_hunk_header() {
+        let patch = Patch::parse("diff --git a/f b/f\n@@ bogus @@\n+a\n-b\n c\n-- \nsig\n");
+        let hunk = &patch.files[0].hunks[0];
+
+        assert_eq!(hunk.old, None);
+        assert_eq!(hunk.lines.len(), 3);
+        assert_eq!(patch.rest, ["-- ", "sig"]);
+    }
+}
@@ -0,0 +1,175 @@
+//! The number of lines added and removed in each file changed by patches, like `git diff --stat`
+
+use super::{Kind, Patch};
+
+/// The changes of each file of a patch, or of a series of patches
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Stat {
+    pub files: Vec<FileStat>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileStat {
+    pub path: String,
+    pub added: usize,
+    pub removed: usize,
+}
+
+impl Stat {
+    /// The changes of a patch, read from its diffstat or counted from its hunks when it has none
+    pub fn of(patch: &Patch) -> Stat {
+        Stat::parse(&patch.preamble).unwrap_or_else(|| Stat::count(patch))
+    }
+
+    /// Reads a diffstat made of lines like ` foo.c | 3 ++-`, `None` when there is none. Git scales
+    /// the bars of large changes, so the split between added and removed lines follows the
+    /// proportion of `+` and `-`
+    pub fn parse(lines: &[&str]) -> Option<Stat> {
+        let mut stat = Stat::default();
+
+        for line in lines {
+            let Some((path, change)) = line.rsplit_once('|') else {
+                continue;
+            };
+            let path = path.trim();
+            let mut change = change.split_whitespace();
+            let (Some(count), bars) = (change.next(), change.next()) else {
+                continue;
+            };
+
+            let (added, removed) = match count.parse::<usize>() {
+                Ok(count) => {
+                    let bars = bars.unwrap_or("");
+                    let plus = bars.chars().filter(|c| *c == '+').count();
+                    let minus = bars.chars().filter(|c| *c == '-').count();
+
+                    if plus + minus != bars.len() {
+                        continue;
+                    }
+
+                    let added = match plus + minus {
+                        0 => 0,
+                        bars => (count * plus + bars / 2) / bars,
+                    };
+
+                    (added, count - added)
+                }
+                // Binary files, like `Bin 0 -> 1234 bytes`
+                Err(_) if count == "Bin" => (0, 0),
+                Err(_) => continue,
+            };
+
+            if path.is_empty() {
+                continue;
+            }
+
+            stat.files.push(FileStat {
+                path: path.to_string(),
+                added,
+                removed,
+            });
+        }
+
+        (!stat.files.is_empty()).then_some(stat)
+    }
+
+    /// Counts the added and removed lines of the hunks of each file
+    pub fn count(patch: &Patch) -> Stat {
+        let files = patch
+            .files
+            .iter()
+            .map(|file| {
+                let lines = file.hunks.iter().flat_map(|hunk| &hunk.lines);
+                let count = |kind| lines.clone().filter(|line| line.kind == kind).count();
+
+                FileStat {
+                    path: file.path().unwrap_or("?").to_string(),
+                    added: count(Kind::Added),
+                    removed: count(Kind::Removed),
+                }
+            })
+            .collect();
+
+        Stat { files }
+    }
+
+    /// Adds the changes of `other`, those to files already changed are added to them
+    pub fn add(&mut self, other: &Stat) {
+        for file in &other.files {
+            match self.files.iter_mut().find(|known| known.path == file.path) {
+                Some(known) => {
+                    known.added += file.added;
+                    known.removed += file.removed;
+                }
+                None => self.files.push(file.clone()),
+            }
+        }
+    }
+
+    pub fn added(&self) -> usize {
+        self.files.iter().map(|file| file.added).sum()
+    }
+
+    pub fn removed(&self) -> usize {
+        self.files.iter().map(|file| file.removed).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str, added: usize, removed: usize) -> FileStat {
+        FileStat {
+            path: path.to_string(),
+            added,
+            removed,
+        }
+    }
+
+    #[test]
+    fn parse_diffstat() {
+        let stat = Stat::parse(&[
+            "foo.c      |  3 ++-",
+            " lib/bar.h | 40 ++++++++++----------",
+            " logo.png  | Bin 0 -> 1234 bytes",
+            " 3 files changed, 24 insertions(+), 19 deletions(-)",
+        ]);
+
+        assert_eq!(
+            stat,
+            Some(Stat {
+                files: vec![
+                    file("foo.c", 2, 1),
+                    file("lib/bar.h", 20, 20),
+                    file("logo.png", 0, 0),
+                ]
+            })
+        );
+        assert_eq!(Stat::parse(&["Changes since v1:", "- fixed | typo"]), None);
+    }
+
+    #[test]
+    fn count_hunks() {
+        let patch = Patch::parse(
+            "diff --git a/foo.c b/foo.c\n--- a/foo.c\n+++ b/foo.c\n@@ -1,2 +1,3 @@\n a\n-b\n+c\n+d\ndiff --git a/bar.c b/bar.c\n--- a/bar.c\n+++ /dev/null\n@@ -1 +0,0 @@\n-x\n",
+        );
+        let mut stat = Stat::of(&patch);
+
+        assert_eq!(stat.files, [file("foo.c", 2, 1), file("bar.c", 0, 1)]);
+
+        stat.add(&Stat {
+            files: vec![file("foo.c", 1, 1), file("baz.c", 5, 0)],
+        });
+
+        assert_eq!(
+            stat.files,
+            [
+                file("foo.c", 3, 2),
+                file("bar.c", 0, 1),
+                file("baz.c", 5, 0)
+            ]
+        );
+        assert_eq!((stat.added(), stat.removed()), (8, 3));
+    }
+}
@@ -0,0 +1,243 @@
+//! The words changed between a removed line and the added line replacing it, to emphasize them
+//! like `git diff --word-diff` or diff-highlight
+
+use std::ops::Range;
+
+use regex::Regex;
+
+use super::{Hunk, Kind};
+
+/// Above this many pairs of tokens compared, lines are left without emphasis
+const MAX_COMPARISONS: usize = 100_000;
+
+/// How lines are split in the tokens compared
+#[derive(Debug, Clone)]
+pub enum Tokenizer {
+    /// Runs of letters, digits and `_`, runs of whitespace and single punctuation characters
+    Word,
+    Char,
+    /// The matches of the regex, and each text between two matches
+    Regex(Regex),
+}
+
+/// The changed ranges of a removed line and of the added line paired with it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Changes {
+    pub removed: Vec<Range<usize>>,
+    pub added: Vec<Range<usize>>,
+}
+
+impl Tokenizer {
+    /// The ranges of the tokens of `text`, covering all of it
+    pub fn tokens(&self, text: &str) -> Vec<Range<usize>> {
+        match self {
+            Tokenizer::Word => {
+                let class = |c: char| match c {
+                    c if c.is_alphanumeric() || c == '_' => 0,
+                    c if c.is_whitespace() => 1,
+                    _ => 2,
+                };
+                let mut tokens: Vec<Range<usize>> = Vec::new();
+                let mut previous = None;
+
+                for (at, c) in text.char_indices() {
+                    let end = at + c.len_utf8();
+
+                    match tokens.last_mut() {
+                        Some(last) if previous == Some(class(c)) && class(c) != 2 => last.end = end,
+                        _ => tokens.push(at..end),
+                    }
+
+                    previous = Some(class(c));
+                }
+
+                tokens
+            }
+            Tokenizer::Char => text
+                .char_indices()
+                .map(|(at, c)| at..at + c.len_utf8())
+                .collect(),
+            Tokenizer::Regex(regex) => {
+                let mut tokens = Vec::new();
+                let mut at = 0;
+
+                for found in regex.find_iter(text).filter(|found| !found.is_empty()) {
+                    if found.start() > at {
+                        tokens.push(at..found.start());
+                    }
+
+                    tokens.push(found.range());
+                    at = found.end();
+                }
+
+                if at < text.len() {
+                    tokens.push(at..text.len());
+                }
+
+                tokens
+            }
+        }
+    }
+}
+
+/// Pairs the lines of the runs of removals with those of the additions following them: the first
+/// removed line with the first added line and so on. The pairs are indexes in `hunk.lines`
+pub fn pairs(hunk: &Hunk) -> Vec<(usize, usize)> {
+    let mut pairs = Vec::new();
+    let mut index = 0;
+
+    while index < hunk.lines.len() {
+        let removed = run(hunk, index, Kind::Removed);
+        let added = run(hunk, removed.end, Kind::Added);
+
+        pairs.extend(removed.clone().zip(added.clone()));
+        index = added.end.max(index + 1);
+    }
+
+    pairs
+}
+
+/// The lines of `kind` starting at `start`, skipping the `\ No newline at end of file` lines
+fn run(hunk: &Hunk, start: usize, kind: Kind) -> Range<usize> {
+    let mut end = start;
+
+    while hunk
+        .lines
+        .get(end)
+        .is_some_and(|line| line.kind == kind || end > start && line.kind == Kind::NoNewline)
+    {
+        end += 1;
+    }
+
+    // A run ends with a line of its kind
+    while end > start && hunk.lines[end - 1].kind == Kind::NoNewline {
+        end -= 1;
+    }
+
+    start..end
+}
+
+/// The ranges that differ between `removed` and `added`, found from their longest common
+/// sequence of tokens. Lines sharing less than half of their text aren't a change of one into
+/// the other and get `None`
+pub fn changes(removed: &str, added: &str, tokenizer: &Tokenizer) -> Option<Changes> {
+    let old = tokenizer.tokens(removed);
+    let new = tokenizer.tokens(added);
+
+    if old.len().saturating_mul(new.len()) > MAX_COMPARISONS {
+        return None;
+    }
+
+    // lengths[i][j] is the length of the longest common sequence of old[i..] and new[j..]
+    let mut lengths = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i][j] = if removed[old[i].clone()] == added[new[j].clone()] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut changes = Changes {
+        removed: Vec::new(),
+        added: Vec::new(),
+    };
+    let mut common = 0;
+    let (mut i, mut j) = (0, 0);
+
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && removed[old[i].clone()] == added[new[j].clone()] {
+            common += old[i].len();
+            i += 1;
+            j += 1;
+        } else if j == new.len() || i < old.len() && lengths[i + 1][j] >= lengths[i][j + 1] {
+            push(&mut changes.removed, old[i].clone());
+            i += 1;
+        } else {
+            push(&mut changes.added, new[j].clone());
+            j += 1;
+        }
+    }
+
+    (common * 4 >= removed.len() + added.len()).then_some(changes)
+}
+
+/// Adds a range, merging it with the last one when they touch
+fn push(ranges: &mut Vec<Range<usize>>, range: Range<usize>) {
+    match ranges.last_mut() {
+        Some(last) if last.end == range.start => last.end = range.end,
+        _ => ranges.push(range),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::Patch;
+
+    fn words(text: &str, tokenizer: &Tokenizer) -> Vec<String> {
+        tokenizer
+            .tokens(text)
+            .into_iter()
+            .map(|range| text[range].to_string())
+            .collect()
+    }
+
+    #[test]
+    fn tokenize() {
+        assert_eq!(
+            words("foo_bar(x,  1);", &Tokenizer::Word),
+            ["foo_bar", "(", "x", ",", "  ", "1", ")", ";"]
+        );
+        assert_eq!(words("añb", &Tokenizer::Char), ["a", "ñ", "b"]);
+        assert_eq!(
+            words("a->b = c", &Tokenizer::Regex(Regex::new(r"\w+").unwrap())),
+            ["a", "->", "b", " = ", "c"]
+        );
+    }
+
+    #[test]
+    fn pair_lines() {
+        let patch = Patch::parse(
+            "diff --git a/f b/f\n@@ -1,5 +1,4 @@\n-a\n-b\n+A\n c\n-d\n-e\n+D\n\\ No newline at end of file\n",
+        );
+
+        assert_eq!(pairs(&patch.files[0].hunks[0]), [(0, 2), (4, 6)]);
+    }
+
+    /// The changed text of both lines
+    fn changed(removed: &str, added: &str, tokenizer: &Tokenizer) -> Option<[Vec<String>; 2]> {
+        let changes = changes(removed, added, tokenizer)?;
+        let texts = |line: &str, ranges: Vec<Range<usize>>| {
+            ranges
+                .into_iter()
+                .map(|range| line[range].to_string())
+                .collect()
+        };
+
+        Some([texts(removed, changes.removed), texts(added, changes.added)])
+    }
+
+    #[test]
+    fn find_changes() {
+        assert_eq!(
+            changed(
+                "\tret = foo(dev, 1);",
+                "\tret = foo_locked(dev, 2);",
+                &Tokenizer::Word
+            ),
+            Some([
+                vec!["foo".into(), "1".into()],
+                vec!["foo_locked".into(), "2".into()]
+            ])
+        );
+        assert_eq!(
+            changed("int x;", "int y;", &Tokenizer::Char),
+            Some([vec!["x".into()], vec!["y".into()]])
+        );
+        assert_eq!(changed("return 0;", "kfree(buf);", &Tokenizer::Word), None);
+    }
+}
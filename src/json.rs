@@ -0,0 +1,199 @@
+//! JSON output of parsed mailboxes
+//!
+//! Two layouts are available, both tagged with [`SCHEMA_VERSION`] so consumers can detect
+//! breaking changes. The version is bumped whenever a field is renamed, removed or changes type,
+//! adding fields doesn't bump it
+//!
+//! - [`write_document`] writes a single JSON document with every mailbox
+//!   `{ "schema_version": 1, "mailboxes": [{ "source": "...", "messages": [...] }] }`
+//! - [`write_lines`] writes one JSON document per line and message (JSON Lines), suited for
+//!   streaming `{ "schema_version": 1, "source": "...", "index": 0, "message": {...} }`
+//!
+//! Messages are serialized with the [`serde::Serialize`] implementations of the mailbox types,
+//! see the README for the description of each of them
+
+use std::io::Write;
+
+use serde::Serialize;
+
+use crate::mailbox::{message::Message, Mailbox};
+
+/// The version of the JSON schema
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize)]
+struct Document<'a> {
+    schema_version: u32,
+    mailboxes: Vec<Source<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+struct Source<'a> {
+    source: &'a str,
+    #[serde(flatten)]
+    mailbox: &'a Mailbox<'a>,
+}
+
+#[derive(Debug, Serialize)]
+struct Line<'a> {
+    schema_version: u32,
+    source: &'a str,
+    index: usize,
+    message: &'a Message<'a>,
+}
+
+/// Writes every mailbox, paired with the name of its source, as a single JSON document
+pub fn write_document<'a>(
+    out: &mut impl Write,
+    mailboxes: impl IntoIterator<Item = (&'a str, &'a Mailbox<'a>)>,
+) -> serde_json::Result<()> {
+    let document = Document {
+        schema_version: SCHEMA_VERSION,
+        mailboxes: mailboxes
+            .into_iter()
+            .map(|(source, mailbox)| Source { source, mailbox })
+            .collect(),
+    };
+
+    serde_json::to_writer_pretty(&mut *out, &document)?;
+    writeln!(out).map_err(serde_json::Error::io)
+}
+
+/// Writes each message of `mailbox` as a JSON document in its own line
+pub fn write_lines(
+    out: &mut impl Write,
+    source: &str,
+    mailbox: &Mailbox,
+) -> serde_json::Result<()> {
+    for (index, message) in mailbox.messages.iter().enumerate() {
+        let line = Line {
+            schema_version: SCHEMA_VERSION,
+            source,
+            index,
+            message,
+        };
+
+        serde_json::to_writer(&mut *out, &line)?;
+        writeln!(out).map_err(serde_json::Error::io)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    #[test]
+    fn json_document() {
+        let input = include_str!("mailbox/samples/multi_patches.mbx");
+        let mailbox = Mailbox::try_from(input).unwrap();
+        let mut out = Vec::new();
+        write_document(&mut out, [("multi_patches.mbx", &mailbox)]).unwrap();
+
+        let document: Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(document["schema_version"], json!(SCHEMA_VERSION));
+        assert_eq!(document["mailboxes"][0]["source"], json!("multi_patches.mbx"));
+
+        let message = &document["mailboxes"][0]["messages"][0];
+        assert_eq!(
+            message["mailer"],
+            json!({ "daemon": "git@z", "date": "1970-01-01T00:00:00Z" })
+        );
+        assert_eq!(
+            message["headers"][0],
+            json!({
+                "kind": "subject",
+                "value": {
+                    "kind": "patch",
+                    "value": {
+                        "version": 1,
+                        "index": [1, 10],
+                        "tags": ["patch-tree"],
+                        "description": "foo message"
+                    }
+                }
+            })
+        );
+        assert_eq!(
+            message["headers"][1],
+            json!({
+                "kind": "from",
+                "value": {
+                    "name": "John Doe",
+                    "email": { "user": "john.doe", "domain": "email.com" }
+                }
+            })
+        );
+        assert_eq!(
+            message["headers"][3],
+            json!({
+                "kind": "message_id",
+                "value": "20220608-john-doe@email.com"
+            })
+        );
+        assert_eq!(
+            message["headers"][4],
+            json!({
+                "kind": "other",
+                "value": { "name": "MIME-Version", "value": "1.0" }
+            })
+        );
+        assert_eq!(message["body"]["kind"], json!("with_front_matter"));
+        assert_eq!(
+            message["body"]["value"]["footers"],
+            json!([{ "key": "Signed-off-by", "value": "John Doe <john.doe(a)email.com>" }])
+        );
+    }
+
+    #[test]
+    fn json_footers() {
+        let input = "From git@z Thu Jan  1 00:00:00 1970
+Subject: [PATCH] foo
+From: John Doe <john.doe@email.com>
+
+Message body
+
+Signed-off-by: John Doe <john.doe@email.com>
+Reviewed-by: Jane Doe <jane.doe@email.com>
+---
+ foo.c | 1 +
+";
+        let mailbox = Mailbox::try_from(input).unwrap();
+        let mut out = Vec::new();
+        write_lines(&mut out, "footers.mbx", &mailbox).unwrap();
+
+        let line: Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(
+            line["message"]["body"]["value"]["footers"],
+            json!([
+                { "key": "Signed-off-by", "value": "John Doe <john.doe@email.com>" },
+                { "key": "Reviewed-by", "value": "Jane Doe <jane.doe@email.com>" }
+            ])
+        );
+    }
+
+    #[test]
+    fn json_lines() {
+        let input = include_str!("mailbox/samples/multi_patches.mbx");
+        let mailbox = Mailbox::try_from(input).unwrap();
+        let mut out = Vec::new();
+        write_lines(&mut out, "multi_patches.mbx", &mailbox).unwrap();
+
+        let lines = String::from_utf8(out).unwrap();
+        let lines = lines
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(lines.len(), mailbox.messages.len());
+
+        for (index, line) in lines.iter().enumerate() {
+            assert_eq!(line["schema_version"], json!(SCHEMA_VERSION));
+            assert_eq!(line["index"], json!(index));
+            assert_eq!(line["source"], json!("multi_patches.mbx"));
+        }
+    }
+}
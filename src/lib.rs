@@ -1,2 +1,11 @@
+pub mod archive;
+pub mod diff;
+#[cfg(feature = "json")]
+pub mod json;
+pub mod lint;
 pub mod mailbox;
+pub mod query;
+pub mod render;
+pub mod syntax;
 pub mod utils;
+pub mod whitespace;
@@ -0,0 +1,738 @@
+//! Checks on patches before they are sent, in the spirit of the kernel's `checkpatch.pl`
+
+use std::{collections::BTreeSet, str::FromStr};
+
+use color_eyre::eyre::eyre;
+
+use crate::{
+    mailbox::{
+        message::{
+            body,
+            header::{Person, Subject},
+            Message,
+        },
+        thread::Threads,
+    },
+    whitespace::{self, Rules},
+};
+
+/// The longest summary, the subject without its `[PATCH]` prefix, accepted
+pub const SUMMARY_WIDTH: usize = 75;
+
+/// The headers that can start the body of a patch to override those of the email
+const IN_BODY_HEADERS: [&str; 3] = ["From:", "Subject:", "Date:"];
+
+/// Series longer than this get a single problem counting their missing patches, rather than a
+/// problem for each of them
+const MAX_LISTED_PATCHES: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum Check {
+    /// The author signed the patch off
+    SignedOffBy,
+    /// Each `Co-developed-by` is followed by the `Signed-off-by` of the same person
+    CoDevelopedBy,
+    /// The sender's `Signed-off-by` is the last one
+    SignOffOrder,
+    /// The summary is neither empty nor longer than [`SUMMARY_WIDTH`]
+    SubjectLength,
+    /// An empty line follows the in-body headers
+    BlankLine,
+    /// The trailers are `Key: value` lines in their own paragraph, closing the commit message
+    TrailerFormat,
+    /// No Gerrit `Change-Id` is left in the commit message
+    ChangeId,
+    /// The added lines have no whitespace errors, see [`whitespace`]
+    Whitespace,
+    /// Every patch of a series has the same version
+    SeriesVersion,
+    /// Every patch of a series has the same total and the indexes go from 1 to N once
+    SeriesIndex,
+    /// Series of more than one patch have a cover letter
+    CoverLetter,
+}
+
+impl Check {
+    pub const ALL: [Check; 11] = [
+        Check::SignedOffBy,
+        Check::CoDevelopedBy,
+        Check::SignOffOrder,
+        Check::SubjectLength,
+        Check::BlankLine,
+        Check::TrailerFormat,
+        Check::ChangeId,
+        Check::Whitespace,
+        Check::SeriesVersion,
+        Check::SeriesIndex,
+        Check::CoverLetter,
+    ];
+
+    /// The name of the check in reports
+    pub fn name(self) -> &'static str {
+        match self {
+            Check::SignedOffBy => "signed-off-by",
+            Check::CoDevelopedBy => "co-developed-by",
+            Check::SignOffOrder => "sign-off-order",
+            Check::SubjectLength => "subject-length",
+            Check::BlankLine => "blank-line",
+            Check::TrailerFormat => "trailer-format",
+            Check::ChangeId => "change-id",
+            Check::Whitespace => "whitespace",
+            Check::SeriesVersion => "series-version",
+            Check::SeriesIndex => "series-index",
+            Check::CoverLetter => "cover-letter",
+        }
+    }
+}
+
+impl FromStr for Check {
+    type Err = color_eyre::Report;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Check::ALL
+            .into_iter()
+            .find(|check| check.name() == value.trim())
+            .ok_or_else(|| {
+                let names = Check::ALL.map(Check::name);
+                eyre!(
+                    "Unknown check `{}`, expected one of {}",
+                    value,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Problem {
+    /// The index of the message with the problem
+    pub message: usize,
+    pub check: Check,
+    pub description: String,
+}
+
+/// Checks the patches among `messages`, ordered by message. Cover letters are only checked as
+/// part of their series, other messages aren't checked. Only the whitespace errors of
+/// `whitespace` are reported
+pub fn lint(messages: &[Message], whitespace: Rules) -> Vec<Problem> {
+    let mut problems = Vec::new();
+
+    for (index, message) in messages.iter().enumerate() {
+        if message.is_patch() && !message.is_cover_letter() {
+            patch(index, message, whitespace, &mut problems);
+        }
+    }
+
+    let threads = Threads::new(messages);
+
+    for index in 0..messages.len() {
+        if is_series_start(messages, &threads, index) {
+            series(messages, &threads, index, &mut problems);
+        }
+    }
+
+    problems.sort_by_key(|problem| (problem.message, problem.check));
+    problems
+}
+
+/// The commit message of a patch: its body up to the `---` line
+pub fn commit_message<'input>(message: &Message<'input>) -> &'input str {
+    let body = message
+        .raw
+        .split_once("\n\n")
+        .map_or("", |(_, body)| body)
+        .trim_start_matches('\n');
+
+    body.split_once("\n---\n")
+        .map_or(body, |(message, _)| message)
+}
+
+/// The part of a patch after its commit message: the diffstat, the diff and the signature
+pub fn diff<'input>(message: &Message<'input>) -> &'input str {
+    message
+        .raw
+        .split_once("\n---\n")
+        .map_or("", |(_, diff)| diff)
+}
+
+/// The author of a patch: the person of the in-body `From:` header, or of the email
+pub fn author<'input>(message: &Message<'input>) -> Option<Person<'input>> {
+    commit_message(message)
+        .lines()
+        .take_while(|line| is_in_body_header(line))
+        .find_map(|line| line.strip_prefix("From:"))
+        .and_then(|from| Person::try_from(from.trim()).ok())
+        .or_else(|| message.from().cloned())
+}
+
+/// Whether two people have the same address, ignoring case
+pub fn same_address(a: &Person, b: &Person) -> bool {
+    a.email
+        .to_string()
+        .eq_ignore_ascii_case(&b.email.to_string())
+}
+
+/// The trailers of the last paragraph of a commit message, with the lines that look like
+/// trailers but aren't `Key: value`
+pub fn trailer_block(commit_message: &str) -> (Vec<(&str, &str)>, Vec<&str>) {
+    let paragraph = last_paragraph(commit_message);
+    let mut trailers = Vec::new();
+    let mut malformed = Vec::new();
+
+    if paragraph
+        .last()
+        .is_some_and(|line| looks_like_trailer(line))
+    {
+        for line in paragraph
+            .into_iter()
+            .filter(|line| looks_like_trailer(line))
+        {
+            match body::trailer(line) {
+                Some((key, value)) if !value.is_empty() && line.trim_end() == line => {
+                    trailers.push((key, value))
+                }
+                _ => malformed.push(line),
+            }
+        }
+    }
+
+    (trailers, malformed)
+}
+
+fn patch(index: usize, message: &Message, rules: Rules, problems: &mut Vec<Problem>) {
+    let mut problem = |check, description: String| {
+        problems.push(Problem {
+            message: index,
+            check,
+            description,
+        })
+    };
+
+    if let Some(subject) = message.subject() {
+        let summary = subject.summary();
+        let width = summary.chars().count();
+
+        if summary.trim().is_empty() {
+            problem(Check::SubjectLength, "the summary is empty".to_string());
+        } else if width > SUMMARY_WIDTH {
+            problem(
+                Check::SubjectLength,
+                format!(
+                    "the summary is {} characters long, more than {}",
+                    width, SUMMARY_WIDTH
+                ),
+            );
+        }
+    }
+
+    let text = commit_message(message);
+    let lines = text.lines().collect::<Vec<_>>();
+    let headers = lines
+        .iter()
+        .take_while(|line| is_in_body_header(line))
+        .count();
+
+    if headers > 0
+        && lines
+            .get(headers)
+            .is_some_and(|line| !line.trim().is_empty())
+    {
+        problem(
+            Check::BlankLine,
+            format!(
+                "no empty line after the in-body `{}`",
+                lines[headers - 1].trim()
+            ),
+        );
+    }
+
+    for line in &lines {
+        let key = line.split_once(':').map_or("", |(key, _)| key.trim());
+
+        if key.eq_ignore_ascii_case("Change-Id") {
+            problem(
+                Check::ChangeId,
+                format!("remove the Gerrit `{}`", line.trim()),
+            );
+        }
+    }
+
+    let paragraph = last_paragraph(text);
+    let (trailers, malformed) = trailer_block(text);
+
+    if !trailers.is_empty() || !malformed.is_empty() {
+        let prose = paragraph
+            .iter()
+            .any(|line| !looks_like_trailer(line) && !is_continuation(line));
+
+        if prose {
+            problem(
+                Check::TrailerFormat,
+                "the trailers aren't separated from the message by an empty line".to_string(),
+            );
+        }
+    }
+
+    for line in malformed {
+        problem(
+            Check::TrailerFormat,
+            format!("malformed trailer `{}`, expected `Key: value`", line),
+        );
+    }
+
+    for (check, description) in sign_off_chain(message) {
+        problem(check, description);
+    }
+
+    for (rule, count) in whitespace::count(diff(message), rules) {
+        problem(
+            Check::Whitespace,
+            format!(
+                "{} added line{} with {}",
+                count,
+                if count == 1 { "" } else { "s" },
+                rule.description()
+            ),
+        );
+    }
+}
+
+/// The problems of the chain of sign-offs of a patch, following the Developer Certificate of
+/// Origin: the author signs the patch off, each `Co-developed-by` is followed by the
+/// `Signed-off-by` of the same person, and the sender's `Signed-off-by` is the last one
+pub fn sign_off_chain(message: &Message) -> Vec<(Check, String)> {
+    let (trailers, _) = trailer_block(commit_message(message));
+    let mut problems = Vec::new();
+    let mut problem = |check, description: String| problems.push((check, description));
+
+    let people = trailers
+        .iter()
+        .map(|(key, value)| {
+            let person = Person::try_from(*value).ok();
+
+            if person.is_none() && is_sign_off(key) {
+                problem(
+                    Check::TrailerFormat,
+                    format!("invalid person in `{}: {}`", key, value),
+                );
+            }
+
+            person
+        })
+        .collect::<Vec<_>>();
+    let sign_offs = trailers
+        .iter()
+        .zip(&people)
+        .filter(|((key, _), _)| key.eq_ignore_ascii_case("Signed-off-by"))
+        .filter_map(|(_, person)| person.as_ref())
+        .collect::<Vec<_>>();
+    let signed = |person: &Person| sign_offs.iter().any(|p| same_address(p, person));
+    let author = author(message);
+
+    match &author {
+        _ if sign_offs.is_empty() => {
+            problem(Check::SignedOffBy, "no `Signed-off-by`".to_string());
+        }
+        Some(author) if !signed(author) => {
+            problem(
+                Check::SignedOffBy,
+                format!("no `Signed-off-by` from the author {}", author),
+            );
+        }
+        _ => {}
+    }
+
+    for (i, ((key, value), person)) in trailers.iter().zip(&people).enumerate() {
+        let Some(developer) = person
+            .as_ref()
+            .filter(|_| key.eq_ignore_ascii_case("Co-developed-by"))
+        else {
+            continue;
+        };
+
+        if author
+            .as_ref()
+            .is_some_and(|author| same_address(author, developer))
+        {
+            problem(
+                Check::CoDevelopedBy,
+                format!("the author {} doesn't need a `Co-developed-by`", developer),
+            );
+        }
+
+        let followed =
+            trailers
+                .get(i + 1)
+                .zip(people.get(i + 1))
+                .is_some_and(|((key, _), person)| {
+                    key.eq_ignore_ascii_case("Signed-off-by")
+                        && person.as_ref().is_some_and(|p| same_address(p, developer))
+                });
+
+        if !followed {
+            problem(
+                Check::CoDevelopedBy,
+                format!(
+                    "`{}: {}` isn't followed by their `Signed-off-by`",
+                    key, value
+                ),
+            );
+        }
+    }
+
+    if let (Some(sender), Some(last)) = (message.from(), sign_offs.last()) {
+        let is_author = author
+            .as_ref()
+            .is_some_and(|author| same_address(author, sender));
+
+        if signed(sender) && !same_address(sender, last) {
+            problem(
+                Check::SignOffOrder,
+                format!(
+                    "the `Signed-off-by` of the sender {} isn't the last one",
+                    sender
+                ),
+            );
+        } else if !signed(sender) && !is_author {
+            problem(
+                Check::SignOffOrder,
+                format!("no `Signed-off-by` from the sender {}", sender),
+            );
+        }
+    }
+
+    problems
+}
+
+/// Whether the trailer names someone taking part in the chain of sign-offs
+fn is_sign_off(key: &str) -> bool {
+    key.eq_ignore_ascii_case("Signed-off-by") || key.eq_ignore_ascii_case("Co-developed-by")
+}
+
+fn is_in_body_header(line: &str) -> bool {
+    IN_BODY_HEADERS
+        .iter()
+        .any(|header| line.starts_with(header))
+}
+
+/// The lines of the last paragraph of `text`
+fn last_paragraph(text: &str) -> Vec<&str> {
+    let lines = text.trim_end().lines().collect::<Vec<_>>();
+    let start = lines
+        .iter()
+        .rposition(|line| line.trim().is_empty())
+        .map_or(0, |empty| empty + 1);
+
+    lines[start..].to_vec()
+}
+
+/// Whether the line starts with a single word followed by a `:`, even if the rest isn't right
+fn looks_like_trailer(line: &str) -> bool {
+    !is_continuation(line)
+        && line.split_once(':').is_some_and(|(key, _)| {
+            let key = key.trim();
+            !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+}
+
+/// Whether the line continues the trailer above it, or comments it like `[ fixed a typo ]`
+fn is_continuation(line: &str) -> bool {
+    line.starts_with([' ', '\t', '['])
+}
+
+/// The version of a patch, patches without one are the first version
+fn patch_version(subject: &Subject) -> Option<usize> {
+    match subject {
+        Subject::Patch { version, .. } => Some(version.unwrap_or(1)),
+        _ => None,
+    }
+}
+
+/// Whether the message starts a series: it is a cover letter, or the first patch when it
+/// doesn't answer a cover letter of the same version
+fn is_series_start(messages: &[Message], threads: &Threads, index: usize) -> bool {
+    match messages[index].subject() {
+        Some(Subject::Patch {
+            index: Some((0, _)),
+            ..
+        }) => true,
+        Some(
+            subject @ Subject::Patch {
+                index: Some((1, _)),
+                ..
+            },
+        ) => !threads.parent(index).is_some_and(|parent| {
+            messages[parent].is_cover_letter()
+                && messages[parent].subject().and_then(patch_version) == patch_version(subject)
+        }),
+        _ => false,
+    }
+}
+
+fn series(messages: &[Message], threads: &Threads, start: usize, problems: &mut Vec<Problem>) {
+    let Some(Subject::Patch {
+        index: Some((first, total)),
+        ..
+    }) = messages[start].subject()
+    else {
+        return;
+    };
+    let version = messages[start].subject().and_then(patch_version);
+    let mut problem = |message, check, description: String| {
+        problems.push(Problem {
+            message,
+            check,
+            description,
+        })
+    };
+
+    // The patches answering the start of the series, and their answers, until another series
+    let mut seen = BTreeSet::new();
+    let mut stack = vec![start];
+
+    while let Some(message) = stack.pop() {
+        if message != start && is_series_start(messages, threads, message) {
+            continue;
+        }
+
+        stack.extend(threads.children(message).iter().rev());
+
+        let Some(
+            subject @ Subject::Patch {
+                index: Some((i, n)),
+                ..
+            },
+        ) = messages[message].subject()
+        else {
+            continue;
+        };
+
+        if patch_version(subject) != version {
+            problem(
+                message,
+                Check::SeriesVersion,
+                format!(
+                    "v{} in a series of v{}",
+                    patch_version(subject).unwrap_or(1),
+                    version.unwrap_or(1)
+                ),
+            );
+        } else if n != total {
+            problem(
+                message,
+                Check::SeriesIndex,
+                format!("{}/{} in a series of {} patches", i, n, total),
+            );
+        } else if *i > *total {
+            problem(
+                message,
+                Check::SeriesIndex,
+                format!("{}/{} is past the end of the series", i, n),
+            );
+        } else if !seen.insert(*i) {
+            problem(
+                message,
+                Check::SeriesIndex,
+                format!("{}/{} was sent more than once", i, n),
+            );
+        }
+    }
+
+    let missing = total - seen.range(1..).count();
+
+    if *total <= MAX_LISTED_PATCHES {
+        for i in (1..=*total).filter(|i| !seen.contains(i)) {
+            problem(
+                start,
+                Check::SeriesIndex,
+                format!("patch {}/{} is missing", i, total),
+            );
+        }
+    } else if missing > 0 {
+        problem(
+            start,
+            Check::SeriesIndex,
+            format!(
+                "{} patches of the {} of the series are missing",
+                missing, total
+            ),
+        );
+    }
+
+    if *total > 1 && *first != 0 {
+        problem(
+            start,
+            Check::CoverLetter,
+            format!("no cover letter for a series of {} patches", total),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mailbox::Mailbox;
+
+    fn problems(input: &str) -> Vec<(usize, &'static str, String)> {
+        let mailbox = Mailbox::try_from(input).unwrap();
+
+        lint(&mailbox.messages, Rules::default())
+            .into_iter()
+            .map(|problem| (problem.message, problem.check.name(), problem.description))
+            .collect()
+    }
+
+    #[test]
+    fn lint_clean_series() {
+        assert_eq!(problems(include_str!("mailbox/samples/thread.mbx")), []);
+    }
+
+    #[test]
+    fn lint_long_series() {
+        for total in ["99999999999999", "18446744073709551615"] {
+            let problems = problems(&format!(
+                "From git@z Thu Jan  1 00:00:00 1970\nFrom: A <a@b.com>\nSubject: [PATCH 1/{}] foo: bar\n\nBar.\n\nSigned-off-by: A <a@b.com>\n---\n",
+                total
+            ));
+
+            assert!(problems.contains(&(
+                0,
+                "series-index",
+                format!(
+                    "{} patches of the {} of the series are missing",
+                    total.parse::<usize>().unwrap() - 1,
+                    total
+                )
+            )));
+        }
+    }
+
+    #[test]
+    fn lint_problems() {
+        let problems = problems(include_str!("mailbox/samples/lint.mbx"));
+        let expected = [
+            (0, "signed-off-by", "no `Signed-off-by` from the author Erin Doe <erin@example.com>"),
+            (0, "subject-length", "the summary is 77 characters long, more than 75"),
+            (0, "blank-line", "no empty line after the in-body `From: Erin Doe <erin@example.com>`"),
+            (0, "series-index", "patch 2/3 is missing"),
+            (0, "series-index", "patch 3/3 is missing"),
+            (0, "cover-letter", "no cover letter for a series of 3 patches"),
+            (1, "signed-off-by", "no `Signed-off-by`"),
+            (1, "trailer-format", "malformed trailer `Signed-off-by:Dave Brown <dave@example.com>`, expected `Key: value`"),
+            (1, "change-id", "remove the Gerrit `Change-Id: I0123456789abcdef0123456789abcdef01234567`"),
+            (1, "series-version", "v4 in a series of v3"),
+            (2, "trailer-format", "the trailers aren't separated from the message by an empty line"),
+            (2, "series-index", "2/5 in a series of 3 patches"),
+        ];
+
+        assert_eq!(
+            problems,
+            expected.map(|(message, check, description)| (message, check, description.to_string()))
+        );
+    }
+
+    #[test]
+    fn lint_sign_off_chains() {
+        assert_eq!(
+            problems(include_str!("mailbox/samples/dco.mbx")),
+            [
+                (
+                    1,
+                    "co-developed-by",
+                    "`Co-developed-by: Bob Jones <bob@kernel.org>` isn't followed by their `Signed-off-by`".to_string()
+                ),
+                (
+                    1,
+                    "sign-off-order",
+                    "the `Signed-off-by` of the sender Alice Smith <alice@example.com> isn't the last one".to_string()
+                ),
+                (
+                    2,
+                    "sign-off-order",
+                    "no `Signed-off-by` from the sender Carol White <carol@corp.example.org>".to_string()
+                ),
+                (
+                    3,
+                    "co-developed-by",
+                    "the author Alice Smith <alice@example.com> doesn't need a `Co-developed-by`".to_string()
+                ),
+                (
+                    3,
+                    "co-developed-by",
+                    "`Co-developed-by: Bob Jones <bob@kernel.org>` isn't followed by their `Signed-off-by`".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn lint_whitespace() {
+        let input = "From git@z Thu Jan  1 00:00:00 1970
+From: Alice Smith <alice@example.com>
+Subject: [PATCH] foo: add bar
+
+Signed-off-by: Alice Smith <alice@example.com>
+---
+ foo.c | 3 +++
+ 1 file changed, 3 insertions(+)
+
+diff --git a/foo.c b/foo.c
+--- a/foo.c
++++ b/foo.c
+@@ -1 +1,4 @@
+ int foo;
++int bar;\t
++int baz; \r
++int qux;
+\\ No newline at end of file
+";
+
+        assert_eq!(
+            problems(input),
+            [
+                (
+                    0,
+                    "whitespace",
+                    "2 added lines with trailing whitespace".to_string()
+                ),
+                (
+                    0,
+                    "whitespace",
+                    "1 added line with no newline at the end of the file".to_string()
+                ),
+                (
+                    0,
+                    "whitespace",
+                    "1 added line with a CRLF line ending".to_string()
+                ),
+            ]
+        );
+
+        let mailbox = Mailbox::try_from(input).unwrap();
+        assert_eq!(lint(&mailbox.messages, Rules::NONE), []);
+    }
+
+    #[test]
+    fn parse_checks() {
+        for check in Check::ALL {
+            assert_eq!(check.name().parse::<Check>().unwrap(), check);
+        }
+
+        assert!("sob".parse::<Check>().is_err());
+    }
+
+    #[test]
+    fn trailer_blocks() {
+        let (trailers, malformed) = trailer_block(
+            "Fix foo.\n\nFixes: 123456 (\"foo\")\n[ a comment ]\nSigned-off-by : A <a@b.c>\n",
+        );
+        assert_eq!(trailers, [("Fixes", "123456 (\"foo\")")]);
+        assert_eq!(malformed, ["Signed-off-by : A <a@b.c>"]);
+
+        let (trailers, malformed) = trailer_block("Fix foo: do less.\nAnd more.");
+        assert!(trailers.is_empty() && malformed.is_empty());
+    }
+}
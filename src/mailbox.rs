@@ -1,4 +1,7 @@
-use std::fmt::Display;
+use std::{
+    fmt::Display,
+    io::{self, Write},
+};
 
 use message::Message;
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
@@ -6,8 +9,10 @@ use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
 use crate::utils;
 
 pub mod message;
+pub mod thread;
 
 #[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Mailbox<'input> {
     pub messages: Vec<Message<'input>>,
 }
@@ -25,6 +30,27 @@ impl<'input> TryFrom<&'input str> for Mailbox<'input> {
     }
 }
 
+/// Writes `messages` as a mbox file, each message exactly as it was read and followed by an empty
+/// line
+pub fn write_mbox<'a, 'input: 'a>(
+    out: &mut impl Write,
+    messages: impl IntoIterator<Item = &'a Message<'input>>,
+) -> io::Result<()> {
+    for message in messages {
+        out.write_all(message.raw.as_bytes())?;
+
+        if !message.raw.ends_with('\n') {
+            writeln!(out)?;
+        }
+
+        if !message.raw.ends_with("\n\n") {
+            writeln!(out)?;
+        }
+    }
+
+    Ok(())
+}
+
 impl Display for Mailbox<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         for message in &self.messages {
@@ -49,4 +75,21 @@ mod tests {
         dbg!(&mailbox);
         assert!(mailbox.messages.len() == 3);
     }
+
+    #[test]
+    fn mbox_round_trip() {
+        let input = include_str!("mailbox/samples/thread.mbx");
+        let mailbox = Mailbox::try_from(input).unwrap();
+
+        let mut out = Vec::new();
+        write_mbox(&mut out, &mailbox.messages).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), format!("{}\n", input));
+
+        let mut out = Vec::new();
+        write_mbox(&mut out, &mailbox.messages[3..4]).unwrap();
+        let reply = String::from_utf8(out).unwrap();
+        assert!(reply.starts_with("From git@z"));
+        assert!(reply.ends_with("Reviewed-by: Bob Jones <bob@kernel.org>\n\n"));
+        assert_eq!(Mailbox::try_from(reply.as_str()).unwrap().messages.len(), 1);
+    }
 }
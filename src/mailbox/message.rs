@@ -3,12 +3,13 @@ use std::fmt::Display;
 use body::Body;
 use chrono::{DateTime, Utc};
 use color_eyre::eyre::{bail, Context};
-use header::Header;
+use header::{Header, Person, Subject};
 
 pub mod body;
 pub mod header;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 /// Represents the mailer line of a message it is used to identify where a new message starts
 /// in a mbox file
 ///
@@ -20,6 +21,7 @@ pub struct Mailer<'input> {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 /// Represents a message in a mbox file
 ///
 /// Is composed of a mailer line, a list of headers and a body
@@ -27,35 +29,30 @@ pub struct Message<'input> {
     pub mailer: Option<Mailer<'input>>,
     pub headers: Vec<Header<'input>>,
     pub body: Body<'input>,
+    /// The message as it was in the mbox file, starting at its `From ` line
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub raw: &'input str,
 }
 
 impl<'input> TryFrom<&'input str> for Message<'input> {
     type Error = color_eyre::Report;
 
     fn try_from(value: &'input str) -> Result<Self, Self::Error> {
-        let mut lines = value.lines();
-        let mut headers = Vec::new();
-        let mut body = "";
-
-        if lines.clone().count() == 0 {
+        if value.is_empty() {
             bail!("Empty message");
         }
 
-        let mailer = lines
-            .next()
-            .map(|line| {
-                Mailer::try_from(line)
-                    .with_context(|| format!("Parsing mailer line `{}`", line.trim()))
-            })
-            .transpose()?;
+        let break_point = value.find("\n\n").unwrap_or(value.len());
+        let (frontmatter, body) = value.split_at(break_point);
+        let (mailer, frontmatter) = frontmatter.split_once('\n').unwrap_or((frontmatter, ""));
 
-        for line in lines {
-            if line.trim().is_empty() {
-                let break_point = value.find("\n\n").unwrap_or(value.len());
-                body = &value[break_point..];
-                break;
-            }
+        let mailer = Some(
+            Mailer::try_from(mailer)
+                .with_context(|| format!("Parsing mailer line `{}`", mailer.trim()))?,
+        );
+        let mut headers = Vec::new();
 
+        for line in unfold(frontmatter) {
             let mut parts = line.splitn(2, ':');
             let key = parts.next().unwrap().trim();
             let value = parts.next().unwrap_or("").trim();
@@ -70,14 +67,144 @@ impl<'input> TryFrom<&'input str> for Message<'input> {
             mailer,
             headers,
             body: body.into(),
+            raw: value,
         })
     }
 }
 
+/// Splits the frontmatter of a message in its headers. A header may span multiple lines when the
+/// lines after the first one start with whitespace (folding)
+fn unfold(frontmatter: &str) -> Vec<&str> {
+    let mut headers = Vec::new();
+    let mut start = 0;
+
+    for (offset, _) in frontmatter.match_indices('\n') {
+        let next = &frontmatter[offset + 1..];
+
+        if !next.starts_with([' ', '\t']) {
+            headers.push(&frontmatter[start..offset]);
+            start = offset + 1;
+        }
+    }
+
+    headers.push(&frontmatter[start..]);
+    headers.retain(|header| !header.trim().is_empty());
+    headers
+}
+
+impl<'input> Message<'input> {
+    pub fn subject(&self) -> Option<&Subject<'input>> {
+        self.headers.iter().find_map(|header| match header {
+            Header::Subject(subject) => Some(subject),
+            _ => None,
+        })
+    }
+
+    pub fn from(&self) -> Option<&Person<'input>> {
+        self.headers.iter().find_map(|header| match header {
+            Header::From(person) => Some(person),
+            _ => None,
+        })
+    }
+
+    /// The date from the `Date` header, or from the mailer line when there's no such header
+    pub fn date(&self) -> Option<DateTime<Utc>> {
+        self.headers
+            .iter()
+            .find_map(|header| match header {
+                Header::Date(date) => Some(*date),
+                _ => None,
+            })
+            .or(self.mailer.as_ref().map(|mailer| mailer.date))
+    }
+
+    pub fn to(&self) -> &[Person<'input>] {
+        self.headers
+            .iter()
+            .find_map(|header| match header {
+                Header::To(people) => Some(people.as_slice()),
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn cc(&self) -> &[Person<'input>] {
+        self.headers
+            .iter()
+            .find_map(|header| match header {
+                Header::Cc(people) => Some(people.as_slice()),
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
+
+    /// The value of the first header named `name` as it is written in the message, folded lines
+    /// included. Unlike [`Message::headers`] nothing is normalized, a `[PATCH 01/12]` subject
+    /// keeps its leading zero
+    pub fn raw_header(&self, name: &str) -> Option<&'input str> {
+        let frontmatter = &self.raw[..self.raw.find("\n\n").unwrap_or(self.raw.len())];
+        let (_, frontmatter) = frontmatter.split_once('\n')?;
+
+        unfold(frontmatter).into_iter().find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            key.trim()
+                .eq_ignore_ascii_case(name)
+                .then_some(value.trim())
+        })
+    }
+
+    pub fn message_id(&self) -> Option<&'input str> {
+        self.headers.iter().find_map(|header| match header {
+            Header::MessageId(id) => Some(*id),
+            _ => None,
+        })
+    }
+
+    pub fn in_reply_to(&self) -> Option<&'input str> {
+        self.headers.iter().find_map(|header| match header {
+            Header::InReplyTo(id) => Some(*id),
+            _ => None,
+        })
+    }
+
+    /// Whether the subject has a `[PATCH]` prefix, cover letters included
+    pub fn is_patch(&self) -> bool {
+        matches!(self.subject(), Some(Subject::Patch { .. }))
+    }
+
+    /// Whether the message is the `[PATCH 0/N]` introducing a series
+    pub fn is_cover_letter(&self) -> bool {
+        matches!(
+            self.subject(),
+            Some(Subject::Patch {
+                index: Some((0, _)),
+                ..
+            })
+        )
+    }
+
+    /// Whether the message answers another one. Patches of a series are sent as replies to their
+    /// cover letter but aren't replies themselves
+    pub fn is_reply(&self) -> bool {
+        !self.is_patch() && (self.in_reply_to().is_some() || !self.references().is_empty())
+    }
+
+    /// The ids of the `References` header, from the oldest to the newest
+    pub fn references(&self) -> &[&'input str] {
+        self.headers
+            .iter()
+            .find_map(|header| match header {
+                Header::References(ids) => Some(ids.as_slice()),
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
+}
+
 impl Display for Message<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         for header in &self.headers {
-            write!(f, "{}\n", header)?;
+            writeln!(f, "{}", header)?;
         }
 
         write!(f, "\n{}", self.body)
@@ -145,6 +272,35 @@ mod tests {
         assert!(message.is_err());
     }
 
+    #[test]
+    fn test_message_folded_headers() {
+        let message = Message::try_from(
+            "From git@z Thu Jan  1 00:00:00 1970\nSubject: foo\nReferences: <a@b.com>\n <c@d.com>\n\t<e@f.com>\nIn-Reply-To: <e@f.com>\n\nbody",
+        )
+        .unwrap();
+
+        assert_eq!(message.headers.len(), 3);
+        assert_eq!(message.references(), ["a@b.com", "c@d.com", "e@f.com"]);
+        assert_eq!(message.in_reply_to(), Some("e@f.com"));
+        assert_eq!(message.subject(), Some(&Subject::Simple("foo")));
+        assert_eq!(message.message_id(), None);
+        assert_eq!(
+            message.raw_header("references"),
+            Some("<a@b.com>\n <c@d.com>\n\t<e@f.com>")
+        );
+        assert_eq!(message.raw_header("Message-Id"), None);
+
+        let message = Message::try_from(
+            "From git@z Thu Jan  1 00:00:00 1970\nSubject: [PATCH v2 01/12]  foo\n\nbody",
+        )
+        .unwrap();
+        assert_eq!(message.raw_header("Subject"), Some("[PATCH v2 01/12]  foo"));
+        assert_eq!(
+            message.subject().unwrap().to_string(),
+            "[PATCH v2 1/12] foo"
+        );
+    }
+
     #[test]
     fn test_message_try_from_git() {
         let message = Message::try_from(include_str!("samples/single_patch.mbx"));
@@ -1,20 +1,49 @@
 use std::fmt::Display;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(tag = "kind", content = "value", rename_all = "snake_case")
+)]
 pub enum Body<'input> {
     Simple(&'input str),
     WithFrontMatter {
         front_matter: &'input str,
+        #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_footers"))]
         footers: Vec<(&'input str, &'input str)>,
         body: &'input str,
     },
     OnlyFrontMatter {
         front_matter: &'input str,
+        #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_footers"))]
         footers: Vec<(&'input str, &'input str)>,
     },
 }
 
-impl Body<'_> {
+impl<'input> Body<'input> {
+    /// The trailers closing the commit message (`Signed-off-by: ...`, `Reviewed-by: ...`) in the
+    /// order they appear. Bodies without a `---` line, like replies, take them from their last
+    /// paragraph when every line of it is a trailer
+    pub fn trailers(&self) -> Vec<(&'input str, &'input str)> {
+        match self {
+            // Footers are read from the bottom up
+            Body::WithFrontMatter { footers, .. } | Body::OnlyFrontMatter { footers, .. } => {
+                footers.iter().rev().copied().collect()
+            }
+            Body::Simple(body) => {
+                let body = body.split("\n-- \n").next().unwrap_or(body).trim_end();
+                let paragraph = body.rsplit("\n\n").next().unwrap_or(body).trim();
+
+                paragraph
+                    .lines()
+                    .map(trailer)
+                    .collect::<Option<Vec<_>>>()
+                    .unwrap_or_default()
+            }
+        }
+    }
+
     pub fn front_matter_only(self) -> Self {
         match self {
             Body::WithFrontMatter {
@@ -30,6 +59,46 @@ impl Body<'_> {
     }
 }
 
+/// Splits a `Key: value` trailer line, keys are single words like `Signed-off-by`
+pub(crate) fn trailer(line: &str) -> Option<(&str, &str)> {
+    let (key, value) = line.split_once(": ")?;
+
+    if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return None;
+    }
+
+    Some((key, value.trim()))
+}
+
+/// Serializes footers as a list of `{ "key": key, "value": value }` in the order of the message
+#[cfg(feature = "serde")]
+fn serialize_footers<S: serde::Serializer>(
+    footers: &[(&str, &str)],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    use serde::ser::{SerializeSeq, SerializeStruct};
+
+    struct Footer<'a>(&'a str, &'a str);
+
+    impl serde::Serialize for Footer<'_> {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let mut footer = serializer.serialize_struct("Footer", 2)?;
+            footer.serialize_field("key", self.0)?;
+            footer.serialize_field("value", self.1)?;
+            footer.end()
+        }
+    }
+
+    let mut seq = serializer.serialize_seq(Some(footers.len()))?;
+
+    // Footers are read from the bottom up
+    for (key, value) in footers.iter().rev() {
+        seq.serialize_element(&Footer(key, value))?;
+    }
+
+    seq.end()
+}
+
 impl<'input> From<&'input str> for Body<'input> {
     fn from(value: &'input str) -> Self {
         if value.contains("\n---\n") {
@@ -56,7 +125,7 @@ impl<'input> From<&'input str> for Body<'input> {
             }
 
             Body::WithFrontMatter {
-                front_matter: &front_matter[..front_matter.len() - consume].trim(),
+                front_matter: front_matter[..front_matter.len() - consume].trim(),
                 footers,
                 body: body.trim(),
             }
@@ -78,7 +147,7 @@ impl Display for Body<'_> {
                 write!(f, "{}\n---\n", front_matter)?;
 
                 for (key, value) in footers {
-                    write!(f, "{}: {}\n", key, value)?;
+                    writeln!(f, "{}: {}", key, value)?;
                 }
 
                 write!(f, "---\n{}", body)
@@ -90,7 +159,7 @@ impl Display for Body<'_> {
                 write!(f, "{}\n---\n", front_matter)?;
 
                 for (key, value) in footers {
-                    write!(f, "{}: {}\n", key, value)?;
+                    writeln!(f, "{}: {}", key, value)?;
                 }
 
                 Ok(())
@@ -98,3 +167,29 @@ impl Display for Body<'_> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn body_trailers() {
+        let body = Body::from(
+            "\nFix foo\n\nSigned-off-by: A <a@b.com>\nReviewed-by: B <b@c.com>\n---\n diff\n",
+        );
+        assert_eq!(
+            body.trailers(),
+            [
+                ("Signed-off-by", "A <a@b.com>"),
+                ("Reviewed-by", "B <b@c.com>")
+            ]
+        );
+
+        let reply =
+            Body::from("\n> quoted: text\n\nLooks good.\n\nAcked-by: B <b@c.com>\n\n-- \nB\n");
+        assert_eq!(reply.trailers(), [("Acked-by", "B <b@c.com>")]);
+
+        let prose = Body::from("\nThe fix: do less\nand more\n");
+        assert!(prose.trailers().is_empty());
+    }
+}
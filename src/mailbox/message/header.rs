@@ -2,26 +2,28 @@ use std::fmt::Display;
 
 use chrono::{DateTime, Utc};
 use color_eyre::eyre::{bail, eyre, Context};
-use color_print::{cformat, cwrite};
-use regex::Regex;
-
-const PERSON_REGEX: &str =
-    r"(?P<name>\w+(\s\w+)*)\s*<(?P<email>[a-zA-Z0-9_.+-]+@[a-zA-Z0-9-]+\.[a-zA-Z0-9-.]+)>";
-const PERSON_NO_NAME_REGEX: &str = r"<?(?P<email>[a-zA-Z0-9_.+-]+@[a-zA-Z0-9-]+\.[a-zA-Z0-9-.]+)>?";
-const PERSON_AT_REGEX: &str = r#"(?P<name>\w+(\s\w+)*)\s*at\s*<"(?P<email>[a-zA-Z0-9_.+-]+ at [a-zA-Z0-9-]+\.[a-zA-Z0-9-.]+)">"#;
-const PERSON_AT_NO_NAME_REGEX: &str =
-    r#"<?"(?P<email>[a-zA-Z0-9_.+-]+ at [a-zA-Z0-9-]+\.[a-zA-Z0-9-.]+)">?"#;
-
-const PATCH_SUBJECT_REGEX: &str = r"^\[PATCH( v(?P<version>\d+))? (?P<index>\d+)/(?P<total>\d+)\] (?P<tags>([^:]+:)*)(?P<description>.+)$";
-const TAGGED_SUBJECT_REGEX: &str = r"^(?P<tags>([^:]+:)+)(?P<description>.+)$";
-const SIMPLE_SUBJECT_REGEX: &str = r"^(?P<description>.+)$";
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(tag = "kind", content = "value", rename_all = "snake_case")
+)]
 pub enum Header<'input> {
     From(Person<'input>),
     Date(DateTime<Utc>),
     Author(Person<'input>),
     Subject(Subject<'input>),
+    /// The id of the message, without the surrounding `< >`
+    MessageId(&'input str),
+    /// The id of the message this one replies to, without the surrounding `< >`
+    InReplyTo(&'input str),
+    /// The ids of the messages in the thread before this one, from the oldest to the newest
+    References(Vec<&'input str>),
+    /// The recipients, headers with addresses that can't be parsed are kept as [`Header::Other`]
+    To(Vec<Person<'input>>),
+    Cc(Vec<Person<'input>>),
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_other"))]
     Other(&'input str, &'input str),
 }
 
@@ -30,16 +32,18 @@ impl<'input> TryFrom<(&'input str, &'input str)> for Header<'input> {
 
     fn try_from(value: (&'input str, &'input str)) -> Result<Self, Self::Error> {
         let (key, value) = value;
-        let lkey = key.to_lowercase();
 
-        match lkey.as_str() {
-            "from" => Ok(Header::From(value.try_into().with_context(|| {
+        // Header names are case insensitive, compare them in place instead of allocating a
+        // lowercase copy for every header line
+        if key.eq_ignore_ascii_case("from") {
+            Ok(Header::From(value.try_into().with_context(|| {
                 format!(
                     "Parsing `From` header in message frontmatter from `{}`",
                     value
                 )
-            })?)),
-            "date" => Ok(Header::Date(
+            })?))
+        } else if key.eq_ignore_ascii_case("date") {
+            Ok(Header::Date(
                 DateTime::parse_from_rfc2822(value)
                     .map(|dt| dt.to_utc())
                     .with_context(|| {
@@ -48,37 +52,162 @@ impl<'input> TryFrom<(&'input str, &'input str)> for Header<'input> {
                             value
                         )
                     })?,
-            )),
-            "author" => Ok(Header::Author(value.try_into().with_context(|| {
+            ))
+        } else if key.eq_ignore_ascii_case("author") {
+            Ok(Header::Author(value.try_into().with_context(|| {
                 format!(
                     "Parsing `Author` header in message frontmatter from `{}`",
                     value
                 )
-            })?)),
-            "subject" => Ok(Header::Subject(value.try_into().with_context(|| {
+            })?))
+        } else if key.eq_ignore_ascii_case("subject") {
+            Ok(Header::Subject(value.try_into().with_context(|| {
                 format!(
                     "Parsing `Subject` header in message frontmatter from `{}`",
                     value
                 )
-            })?)),
-            _ => Ok(Header::Other(key, value)),
+            })?))
+        } else if key.eq_ignore_ascii_case("message-id") {
+            Ok(msg_id(value).map_or(Header::Other(key, value), Header::MessageId))
+        } else if key.eq_ignore_ascii_case("in-reply-to") {
+            Ok(msg_id(value).map_or(Header::Other(key, value), Header::InReplyTo))
+        } else if key.eq_ignore_ascii_case("references") {
+            Ok(Header::References(msg_ids(value).collect()))
+        } else if key.eq_ignore_ascii_case("to") {
+            Ok(addresses(value).map_or(Header::Other(key, value), Header::To))
+        } else if key.eq_ignore_ascii_case("cc") {
+            Ok(addresses(value).map_or(Header::Other(key, value), Header::Cc))
+        } else {
+            Ok(Header::Other(key, value))
         }
     }
 }
 
-impl Display for Header<'_> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+/// The first message id found in `value`. Ids are usually surrounded by `< >` but bare ids are
+/// also accepted
+fn msg_id(value: &str) -> Option<&str> {
+    msg_ids(value).next()
+}
+
+/// Every message id found in `value`, `In-Reply-To` and `References` may have comments after or
+/// between the ids
+fn msg_ids(value: &str) -> impl Iterator<Item = &str> {
+    let bracketed = value.contains('<');
+
+    value
+        .split(move |c: char| {
+            if bracketed {
+                c == '<'
+            } else {
+                c.is_whitespace()
+            }
+        })
+        .skip(usize::from(bracketed))
+        .filter_map(move |id| {
+            let id = if bracketed { id.split('>').next()? } else { id };
+            let id = id.trim();
+
+            (!id.is_empty()).then_some(id)
+        })
+}
+
+/// The people of an address list like `A <a@b.com>, "Doe, John" <john@doe.com>`. `None` when any
+/// of them can't be parsed, group syntax (`undisclosed-recipients:;`) included
+fn addresses(value: &str) -> Option<Vec<Person<'_>>> {
+    let mut people = Vec::new();
+    let mut quoted = false;
+    let mut bracketed = false;
+    let mut start = 0;
+
+    for (i, c) in value.char_indices().chain([(value.len(), ',')]) {
+        match c {
+            '"' => quoted = !quoted,
+            '<' if !quoted => bracketed = true,
+            '>' if !quoted => bracketed = false,
+            ',' if !quoted && !bracketed => {
+                let address = value[start..i].trim();
+
+                if !address.is_empty() {
+                    people.push(Person::try_from(address).ok()?);
+                }
+
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+
+    (!people.is_empty()).then_some(people)
+}
+
+impl Header<'_> {
+    /// The name of the header as written in messages, like `Message-Id`
+    pub fn name(&self) -> &str {
         match self {
-            Header::From(person) => cwrite!(f, "<s><b>From:</b></s> {}", person),
-            Header::Date(date) => cwrite!(f, "<s><g>Date:</g></s> <g>{}</g>", date.to_rfc2822()),
-            Header::Author(person) => cwrite!(f, "<s><r>Author:</r></s> {}", person),
-            Header::Subject(subject) => cwrite!(f, "<s><y>Subject:</y></s> {}", subject),
-            Header::Other(key, value) => cwrite!(f, "<c>{}:</c> {}", key, value),
+            Header::From(_) => "From",
+            Header::Date(_) => "Date",
+            Header::Author(_) => "Author",
+            Header::Subject(_) => "Subject",
+            Header::MessageId(_) => "Message-Id",
+            Header::InReplyTo(_) => "In-Reply-To",
+            Header::References(_) => "References",
+            Header::To(_) => "To",
+            Header::Cc(_) => "Cc",
+            Header::Other(key, _) => key,
         }
     }
+
+    /// The value of the header as it would be written in a message
+    pub fn value(&self) -> String {
+        let ids = |ids: &[&str]| {
+            ids.iter()
+                .map(|id| format!("<{}>", id))
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        let people = |people: &[Person]| {
+            people
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        match self {
+            Header::From(person) | Header::Author(person) => person.to_string(),
+            Header::Date(date) => date.to_rfc2822(),
+            Header::Subject(subject) => subject.to_string(),
+            Header::MessageId(id) | Header::InReplyTo(id) => ids(&[id]),
+            Header::References(references) => ids(references),
+            Header::To(recipients) | Header::Cc(recipients) => people(recipients),
+            Header::Other(_, value) => value.to_string(),
+        }
+    }
+}
+
+/// Serializes [`Header::Other`] as `{ "name": key, "value": value }`
+#[cfg(feature = "serde")]
+fn serialize_other<S: serde::Serializer>(
+    key: &&str,
+    value: &&str,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    use serde::ser::SerializeStruct;
+
+    let mut header = serializer.serialize_struct("Other", 2)?;
+    header.serialize_field("name", key)?;
+    header.serialize_field("value", value)?;
+    header.end()
+}
+
+impl Display for Header<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.name(), self.value())
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Person<'input> {
     pub name: Option<&'input str>,
     pub email: Email<'input>,
@@ -87,30 +216,108 @@ pub struct Person<'input> {
 impl<'input> TryFrom<&'input str> for Person<'input> {
     type Error = color_eyre::Report;
 
+    /// Parses a person in one of the forms found in mailboxes
+    ///
+    /// - `Name <user@domain>`
+    /// - `<user@domain>` or `user@domain`
+    /// - `Name <"user at domain">`, the obfuscated form used by some list archives
+    /// - `<"user at domain">` or `"user at domain"`
     fn try_from(value: &'input str) -> Result<Self, Self::Error> {
-        let re_person = Regex::new(PERSON_REGEX).unwrap();
-        let re_person_no_name = Regex::new(PERSON_NO_NAME_REGEX).unwrap();
-        let re_person_at = Regex::new(PERSON_AT_REGEX).unwrap();
-        let re_person_at_no_name = Regex::new(PERSON_AT_NO_NAME_REGEX).unwrap();
-
-        let captures = re_person
-            .captures(value)
-            .or_else(|| re_person_no_name.captures(value))
-            .or_else(|| re_person_at.captures(value))
-            .or_else(|| re_person_at_no_name.captures(value))
-            .ok_or(eyre!(
-                "Invalid person in `{}`. A person must have an email and optionally a name",
-                value
-            ))?;
-
-        let name = captures.name("name").map(|name| name.as_str());
-        let email = captures
-            .name("email")
-            .ok_or(eyre!("Invalid email in `{}`. A email must be surrounded by `< >` and contain an `@` or ` at `(must be double quoted in this case)", value))?
-            .as_str()
-            .try_into()?;
-
-        Ok(Person { name, email })
+        let (name, address) = split_person(value).ok_or(eyre!(
+            "Invalid person in `{}`. A person must have an email and optionally a name",
+            value
+        ))?;
+
+        if !is_address(address) {
+            bail!("Invalid email in `{}`. A email must be surrounded by `< >` and contain an `@` or ` at `(must be double quoted in this case)", value);
+        }
+
+        Ok(Person {
+            name,
+            email: address.try_into()?,
+        })
+    }
+}
+
+/// Splits a person into its optional name and its address, the address is returned without the
+/// surrounding `< >` and double quotes
+fn split_person(value: &str) -> Option<(Option<&str>, &str)> {
+    let value = value.trim();
+
+    if let Some(open) = value.rfind('<') {
+        let close = open + value[open..].find('>')?;
+        let name = value[..open].trim();
+        // Archives that obfuscate addresses write `Name at <"user at domain">`
+        let name = name.strip_suffix(" at").unwrap_or(name);
+        let name = name.trim().trim_matches('"').trim();
+        let address = value[open + 1..close].trim().trim_matches('"');
+
+        Some(((!name.is_empty()).then_some(name), address))
+    } else if let Some(quoted) = value.strip_prefix('"') {
+        let address = quoted.split('"').next()?;
+
+        Some((None, address))
+    } else {
+        let address = value.split_whitespace().find(|word| word.contains('@'))?;
+
+        Some((None, address))
+    }
+}
+
+/// Checks that `address` is either `user@domain` or `user at domain` where the domain has at least
+/// two labels
+fn is_address(address: &str) -> bool {
+    let Some((user, domain)) = address
+        .split_once('@')
+        .or_else(|| address.split_once(" at "))
+    else {
+        return false;
+    };
+    let Some((label, rest)) = domain.split_once('.') else {
+        return false;
+    };
+
+    !user.is_empty()
+        && user
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '+' | '-'))
+        && !label.is_empty()
+        && !rest.is_empty()
+        && domain
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '.'))
+}
+
+impl Person<'_> {
+    /// Whether the person matches `pattern`, which is either
+    /// - an address (`alice@example.com`), compared ignoring case
+    /// - a domain (`@example.com`), matching its subdomains too. Patterns like `example.com` are
+    ///   matched as a domain first, then like anything else so `john.doe` finds `John.Doe@x.org`
+    /// - anything else, looked for in the name and the address ignoring case
+    pub fn matches(&self, pattern: &str) -> bool {
+        let pattern = pattern.trim();
+
+        if let Some(domain) = pattern.strip_prefix('@') {
+            return self.email.in_domain(domain);
+        }
+
+        if let Some((user, domain)) = pattern.split_once('@') {
+            return self.email.user.eq_ignore_ascii_case(user)
+                && self.email.domain.eq_ignore_ascii_case(domain);
+        }
+
+        if pattern.contains('.')
+            && !pattern.contains(char::is_whitespace)
+            && self.email.in_domain(pattern)
+        {
+            return true;
+        }
+
+        let pattern = pattern.to_lowercase();
+
+        self.name
+            .is_some_and(|name| name.to_lowercase().contains(&pattern))
+            || self.email.to_string().to_lowercase().contains(&pattern)
     }
 }
 
@@ -125,11 +332,22 @@ impl Display for Person<'_> {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Email<'input> {
     pub user: &'input str,
     pub domain: &'input str,
 }
 
+impl Email<'_> {
+    /// Whether the address is in `domain` or one of its subdomains, ignoring case
+    pub fn in_domain(&self, domain: &str) -> bool {
+        let own = self.domain.to_ascii_lowercase();
+        let domain = domain.to_ascii_lowercase();
+
+        own == domain || own.ends_with(&format!(".{}", domain))
+    }
+}
+
 impl<'input> TryFrom<&'input str> for Email<'input> {
     type Error = color_eyre::Report;
 
@@ -153,11 +371,16 @@ impl<'input> TryFrom<&'input str> for Email<'input> {
 
 impl Display for Email<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        cwrite!(f, "<m>{}@{}</m>", self.user, self.domain)
+        write!(f, "{}@{}", self.user, self.domain)
     }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(tag = "kind", content = "value", rename_all = "snake_case")
+)]
 pub enum Subject<'input> {
     Simple(&'input str),
     Tagged {
@@ -175,77 +398,96 @@ pub enum Subject<'input> {
 impl<'input> TryFrom<&'input str> for Subject<'input> {
     type Error = color_eyre::Report;
 
+    /// Parses a subject line in a single pass
+    ///
+    /// - `[PATCH v2 3/10] tag: other: description` is a [`Subject::Patch`], the version and the
+    ///   index are both optional (`[PATCH] description` is also a patch)
+    /// - `tag: other: description` is a [`Subject::Tagged`]
+    /// - anything else is a [`Subject::Simple`]
     fn try_from(value: &'input str) -> Result<Self, Self::Error> {
-        let re_patch = Regex::new(PATCH_SUBJECT_REGEX).unwrap();
-        let re_tagged = Regex::new(TAGGED_SUBJECT_REGEX).unwrap();
-        let re_simple = Regex::new(SIMPLE_SUBJECT_REGEX).unwrap();
-
-        if let Some(captures) = re_patch.captures(value) {
-            let version = captures
-                .name("version")
-                .map(|v| v.as_str().parse::<usize>().ok())
-                .flatten();
-            let base = captures
-                .name("index")
-                .map(|t| t.as_str().parse::<usize>().ok())
-                .flatten();
-            let total = captures
-                .name("total")
-                .map(|t| t.as_str().parse::<usize>().ok())
-                .flatten();
-            let tags = captures
-                .name("tags")
-                .map(|m| m.as_str())
-                .unwrap_or("")
-                .split(':')
-                .map(|s| s.trim())
-                .filter(|s| !s.is_empty())
-                .collect();
-            let description = captures
-                .name("description")
-                .map(|d| d.as_str())
-                .map(|s| s.trim())
-                .ok_or(eyre!("Invalid description"))?;
+        let value = value.trim();
 
-            let index = match (base, total) {
-                (Some(base), Some(total)) => Some((base, total)),
-                _ => None,
-            };
-            Ok(Subject::Patch {
+        if value.is_empty() {
+            bail!("Invalid subject `{}`", value);
+        }
+
+        if let Some((version, index, rest)) = split_patch_prefix(value) {
+            let (tags, description) = split_tags(rest);
+
+            return Ok(Subject::Patch {
                 version,
                 index,
                 tags,
                 description,
-            })
-        } else if let Some(captures) = re_tagged.captures(value) {
-            let tags = captures
-                .name("tags")
-                .ok_or(eyre!(
-                    "Invalid tags in `{}`. Tags are separated by `:` before the actual description",
-                    value
-                ))?
-                .as_str()
-                .split(':')
-                .map(|s| s.trim())
-                .filter(|s| !s.is_empty())
-                .collect();
-            let description = captures
-                .name("description")
-                .map(|d| d.as_str())
-                .map(|s| s.trim())
-                .ok_or(eyre!("Invalid description"))?;
+            });
+        }
 
-            Ok(Subject::Tagged { tags, description })
-        } else if let Some(captures) = re_simple.captures(value) {
-            let description = captures
-                .name("description")
-                .map(|d| d.as_str())
-                .map(|s| s.trim())
-                .ok_or(eyre!("Invalid description"))?;
+        let (tags, description) = split_tags(value);
 
+        if tags.is_empty() {
             Ok(Subject::Simple(description))
         } else {
-            bail!("Invalid subject `{}`", value);
+            Ok(Subject::Tagged { tags, description })
+        }
+    }
+}
+
+/// The version, the index and the remaining text of a subject with a `[PATCH v2 1/3]` prefix
+type PatchPrefix<'input> = (Option<usize>, Option<(usize, usize)>, &'input str);
+
+/// Splits the `[PATCH ...]` prefix of a subject returning the version, the index and the rest of
+/// the subject. Returns `None` if the subject doesn't start with a well formed patch prefix
+fn split_patch_prefix(value: &str) -> Option<PatchPrefix<'_>> {
+    let rest = value.strip_prefix("[PATCH")?;
+    let (prefix, rest) = rest.split_once(']')?;
+    let mut version = None;
+    let mut index = None;
+
+    for word in prefix.split_whitespace() {
+        if let Some(parsed) = word.strip_prefix('v').and_then(|v| v.parse().ok()) {
+            version = Some(parsed);
+        } else if let Some((i, total)) = word.split_once('/') {
+            index = Some((i.parse().ok()?, total.parse().ok()?));
+        } else {
+            return None;
+        }
+    }
+
+    Some((version, index, rest.trim()))
+}
+
+/// Splits the `tag: other:` prefix of a subject from its description. Everything up to the last
+/// `:` that is followed by some text is considered a tag
+fn split_tags(value: &str) -> (Vec<&str>, &str) {
+    let split = value
+        .char_indices()
+        .rev()
+        .filter(|(_, c)| *c == ':')
+        .map(|(i, _)| i)
+        .find(|i| !value[i + 1..].trim().is_empty());
+
+    match split {
+        Some(i) => (
+            value[..i]
+                .split(':')
+                .map(|s| s.trim())
+                .filter(|s| !s.is_empty())
+                .collect(),
+            value[i + 1..].trim(),
+        ),
+        None => (Vec::new(), value),
+    }
+}
+
+impl Subject<'_> {
+    /// The subject without its `[PATCH]` prefix, like the first line of the commit message
+    pub fn summary(&self) -> String {
+        match self {
+            Subject::Patch {
+                tags, description, ..
+            } if !tags.is_empty() => format!("{}: {}", tags.join(": "), description),
+            Subject::Patch { description, .. } => description.to_string(),
+            subject => subject.to_string(),
         }
     }
 }
@@ -263,24 +505,23 @@ impl Display for Subject<'_> {
                 tags,
                 description,
             } => {
-                let version = version.map(|v| format!(" v{}", v)).unwrap_or_default();
-                let index = index
-                    .map(|(i, t)| cformat!(" <r>{}/{}</r>", i, t))
-                    .unwrap_or_default();
-                let tags = if tags.len() > 0 {
-                    format!("|{}| ", tags.join("|"))
-                } else {
-                    "".to_string()
-                };
-
-                cwrite!(
-                    f,
-                    "<y>[PATCH{}</y>{}<y>]</y> <g>{}</>{}",
-                    version,
-                    index,
-                    tags,
-                    description
-                )
+                write!(f, "[PATCH")?;
+
+                if let Some(version) = version {
+                    write!(f, " v{}", version)?;
+                }
+
+                if let Some((index, total)) = index {
+                    write!(f, " {}/{}", index, total)?;
+                }
+
+                write!(f, "] ")?;
+
+                if !tags.is_empty() {
+                    write!(f, "{}: ", tags.join(": "))?;
+                }
+
+                write!(f, "{}", description)
             }
         }
     }
@@ -315,11 +556,85 @@ mod tests {
     #[test]
     fn format_header() {
         let header = Header::Other("SomeHeader", "SomeValue");
-        println!("{}", header);
+        assert_eq!(header.to_string(), "SomeHeader: SomeValue");
+
+        let header = Header::try_from(("From", "Foo Bar <\"foo at bar.com\">")).unwrap();
+        assert_eq!(header.to_string(), "From: Foo Bar <foo@bar.com>");
+    }
+
+    #[test]
+    fn parse_message_id_headers() {
+        assert_eq!(
+            Header::try_from(("Message-ID", " <foo@bar.com> ")).unwrap(),
+            Header::MessageId("foo@bar.com")
+        );
+        assert_eq!(
+            Header::try_from(("In-Reply-To", "<foo@bar.com> (Foo's message)")).unwrap(),
+            Header::InReplyTo("foo@bar.com")
+        );
+        assert_eq!(
+            Header::try_from(("References", "<a@b.com>\n <c@d.com> <e@f.com>")).unwrap(),
+            Header::References(vec!["a@b.com", "c@d.com", "e@f.com"])
+        );
+        assert_eq!(
+            Header::try_from(("References", "a@b.com c@d.com")).unwrap(),
+            Header::References(vec!["a@b.com", "c@d.com"])
+        );
+        assert_eq!(
+            Header::try_from(("Message-Id", "<>")).unwrap(),
+            Header::Other("Message-Id", "<>")
+        );
+        assert_eq!(
+            Header::try_from(("Message-Id", "")).unwrap(),
+            Header::Other("Message-Id", "")
+        );
+        assert_eq!(
+            Header::try_from(("In-Reply-To", " ")).unwrap(),
+            Header::Other("In-Reply-To", " ")
+        );
+
+        assert_eq!(
+            Header::References(vec!["a@b.com", "c@d.com"]).to_string(),
+            "References: <a@b.com> <c@d.com>"
+        );
+    }
+
+    #[test]
+    fn parse_address_lists() {
+        let header =
+            Header::try_from(("To", "\"Doe, John\" <john@doe.com>, list@vger.kernel.org")).unwrap();
+        let Header::To(people) = &header else {
+            panic!("expected a `To` header, got {:?}", header);
+        };
+        assert_eq!(people.len(), 2);
+        assert_eq!(people[0].name, Some("Doe, John"));
+        assert_eq!(people[1].email.domain, "vger.kernel.org");
         assert_eq!(
             header.to_string(),
-            format!("\u{1b}[36mSomeHeader:\u{1b}[39m SomeValue")
+            "To: Doe, John <john@doe.com>, <list@vger.kernel.org>"
         );
+
+        assert_eq!(
+            Header::try_from(("Cc", "undisclosed-recipients:;")).unwrap(),
+            Header::Other("Cc", "undisclosed-recipients:;")
+        );
+    }
+
+    #[test]
+    fn match_people() {
+        let person = Person::try_from("Alice Smith <Alice@Corp.Example.com>").unwrap();
+
+        assert!(person.matches("alice@corp.example.com"));
+        assert!(!person.matches("alice@example.com"));
+        assert!(person.matches("@example.com"));
+        assert!(person.matches("example.com"));
+        assert!(!person.matches("@ample.com"));
+        assert!(!person.matches("example.org"));
+        assert!(Person::try_from("John Doe <john.doe@example.com>")
+            .unwrap()
+            .matches("john.doe"));
+        assert!(person.matches("smith"));
+        assert!(!person.matches("bob"));
     }
 
     #[test]
@@ -332,6 +647,32 @@ mod tests {
         assert_eq!(person.email, "foo.bar@bar.com".try_into().unwrap());
     }
 
+    #[test]
+    fn parse_person_forms() {
+        let person = Person::try_from("<foo.bar@bar.com>").unwrap();
+        assert_eq!(person.name, None);
+        assert_eq!(person.email, "foo.bar@bar.com".try_into().unwrap());
+
+        let person = Person::try_from("foo.bar@bar.com").unwrap();
+        assert_eq!(person.name, None);
+        assert_eq!(person.email, "foo.bar@bar.com".try_into().unwrap());
+
+        let person = Person::try_from("\"Bar, Foo\" <foo.bar@bar.com>").unwrap();
+        assert_eq!(person.name, Some("Bar, Foo"));
+
+        let person = Person::try_from("John Doe <\"john.doe at email.com\">").unwrap();
+        assert_eq!(person.name, Some("John Doe"));
+        assert_eq!(person.email, "john.doe at email.com".try_into().unwrap());
+
+        let person = Person::try_from("\"john.doe at email.com\"").unwrap();
+        assert_eq!(person.name, None);
+        assert_eq!(person.email.domain, "email.com");
+
+        assert!(Person::try_from("Foo Bar").is_err());
+        assert!(Person::try_from("Foo Bar <foo.bar>").is_err());
+        assert!(Person::try_from("Foo Bar <foo@bar>").is_err());
+    }
+
     #[test]
     fn parse_subject() {
         let subject = Subject::try_from("[PATCH v1 1/1] foo: bar: baz");
@@ -375,6 +716,47 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parse_subject_forms() {
+        assert_eq!(
+            Subject::try_from("[PATCH] foo: bar").unwrap(),
+            Subject::Patch {
+                version: None,
+                index: None,
+                tags: vec!["foo"],
+                description: "bar"
+            }
+        );
+
+        assert_eq!(
+            Subject::try_from("[PATCH v3] bar").unwrap(),
+            Subject::Patch {
+                version: Some(3),
+                index: None,
+                tags: vec![],
+                description: "bar"
+            }
+        );
+
+        assert_eq!(
+            Subject::try_from("foo: bar:").unwrap(),
+            Subject::Tagged {
+                tags: vec!["foo"],
+                description: "bar:"
+            }
+        );
+
+        assert_eq!(
+            Subject::try_from("[PATCHES] foo").unwrap(),
+            Subject::Simple("[PATCHES] foo")
+        );
+        assert_eq!(
+            Subject::try_from("[PATCH x/2] foo").unwrap(),
+            Subject::Simple("[PATCH x/2] foo")
+        );
+        assert!(Subject::try_from("  ").is_err());
+    }
+
     #[test]
     fn format_subject() {
         let subject = Subject::Patch {
@@ -383,7 +765,8 @@ mod tests {
             tags: vec!["foo", "bar"],
             description: "baz",
         };
-        assert_eq!(subject.to_string(), "\u{1b}[33m[PATCH v1\u{1b}[39m \u{1b}[31m1/1\u{1b}[39m\u{1b}[33m]\u{1b}[39m \u{1b}[32m|foo|bar| \u{1b}[39mbaz");
+        assert_eq!(subject.to_string(), "[PATCH v1 1/1] foo: bar: baz");
+        assert_eq!(subject.summary(), "foo: bar: baz");
 
         let subject = Subject::Patch {
             version: None,
@@ -391,13 +774,15 @@ mod tests {
             tags: vec![],
             description: "some example patch",
         };
-        assert_eq!(subject.to_string(), "\u{1b}[33m[PATCH\u{1b}[39m \u{1b}[31m0/2\u{1b}[39m\u{1b}[33m]\u{1b}[39m \u{1b}[32m\u{1b}[39msome example patch");
+        assert_eq!(subject.to_string(), "[PATCH 0/2] some example patch");
+        assert_eq!(subject.summary(), "some example patch");
 
         let subject = Subject::Tagged {
             tags: vec!["foo"],
             description: "bar",
         };
         assert_eq!(subject.to_string(), "foo: bar");
+        assert_eq!(subject.summary(), "foo: bar");
 
         let subject = Subject::Simple("baz foo barbar");
         assert_eq!(subject.to_string(), "baz foo barbar");
@@ -0,0 +1,241 @@
+use std::collections::HashMap;
+
+use super::message::{header::Subject, Message};
+
+/// The reply structure of a list of messages, built from their `Message-Id`, `In-Reply-To` and
+/// `References` headers. Messages are referred to by their position in the list
+///
+/// A message replies to the message of its `In-Reply-To` header, or to the newest message of its
+/// `References` that is in the list. Messages replying to something that isn't in the list are
+/// the roots of their own threads
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Threads {
+    parents: Vec<Option<usize>>,
+    children: Vec<Vec<usize>>,
+    roots: Vec<usize>,
+}
+
+/// A patch series: the patches of a thread that share the same version and number of patches
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Series {
+    pub version: Option<usize>,
+    pub total: usize,
+    /// The `[PATCH 0/N]` message, if the series has one
+    pub cover: Option<usize>,
+    /// The patches ordered by their index
+    pub patches: Vec<usize>,
+}
+
+impl Threads {
+    pub fn new(messages: &[Message]) -> Self {
+        let mut ids = HashMap::new();
+
+        // When the same id is used twice, the first message keeps it
+        for (index, message) in messages.iter().enumerate() {
+            if let Some(id) = message.message_id() {
+                ids.entry(id).or_insert(index);
+            }
+        }
+
+        let mut parents = vec![None; messages.len()];
+
+        for (index, message) in messages.iter().enumerate() {
+            let parent = message
+                .in_reply_to()
+                .into_iter()
+                .chain(message.references().iter().rev().copied())
+                .find_map(|id| ids.get(id).copied())
+                .filter(|parent| *parent != index);
+
+            // Broken headers may point at each other, the reply that would close a cycle is
+            // treated as a root
+            if let Some(parent) = parent {
+                if !ancestors(&parents, parent).any(|ancestor| ancestor == index) {
+                    parents[index] = Some(parent);
+                }
+            }
+        }
+
+        let mut children = vec![Vec::new(); messages.len()];
+        let mut roots = Vec::new();
+
+        for (index, parent) in parents.iter().enumerate() {
+            match parent {
+                Some(parent) => children[*parent].push(index),
+                None => roots.push(index),
+            }
+        }
+
+        let dates = messages.iter().map(Message::date).collect::<Vec<_>>();
+
+        for replies in &mut children {
+            replies.sort_by_key(|index| (dates[*index], *index));
+        }
+
+        let mut threads = Threads {
+            parents,
+            children,
+            roots,
+        };
+
+        let latest = threads
+            .roots
+            .iter()
+            .map(|root| {
+                let latest = threads.thread(*root).into_iter().map(|i| dates[i]).max();
+                (*root, latest.flatten())
+            })
+            .collect::<HashMap<_, _>>();
+
+        threads
+            .roots
+            .sort_by_key(|root| (std::cmp::Reverse(latest[root]), *root));
+        threads
+    }
+
+    /// The first message of each thread, the thread with the newest message comes first
+    pub fn roots(&self) -> &[usize] {
+        &self.roots
+    }
+
+    /// The message `index` replies to
+    pub fn parent(&self, index: usize) -> Option<usize> {
+        self.parents[index]
+    }
+
+    /// The replies to the message `index`, from the oldest to the newest
+    pub fn children(&self, index: usize) -> &[usize] {
+        &self.children[index]
+    }
+
+    /// The first message of the thread of `index`
+    pub fn root(&self, index: usize) -> usize {
+        ancestors(&self.parents, index).last().unwrap_or(index)
+    }
+
+    /// How many replies separate `index` from the root of its thread
+    pub fn depth(&self, index: usize) -> usize {
+        ancestors(&self.parents, index).count() - 1
+    }
+
+    /// Every message in the thread starting at `root`, each message followed by its replies
+    pub fn thread(&self, root: usize) -> Vec<usize> {
+        let mut thread = Vec::new();
+        let mut stack = vec![root];
+
+        while let Some(index) = stack.pop() {
+            thread.push(index);
+            stack.extend(self.children[index].iter().rev());
+        }
+
+        thread
+    }
+
+    /// Every message, thread after thread
+    pub fn order(&self) -> Vec<usize> {
+        self.roots
+            .iter()
+            .flat_map(|root| self.thread(*root))
+            .collect()
+    }
+
+    /// The patch series started by `root`. The root is either a cover letter (`[PATCH 0/N]`) or
+    /// the first patch, the other patches of the series are looked for among its replies.
+    /// Replies with other versions or totals, and repeated indexes, are left out
+    pub fn series(&self, messages: &[Message], root: usize) -> Option<Series> {
+        let (version, index) = match messages[root].subject()? {
+            Subject::Patch { version, index, .. } => (*version, *index),
+            _ => return None,
+        };
+
+        let Some((first, total)) = index else {
+            return Some(Series {
+                version,
+                total: 1,
+                cover: None,
+                patches: vec![root],
+            });
+        };
+
+        if first > 1 {
+            return None;
+        }
+
+        let mut patches = Vec::new();
+
+        for message in self.thread(root) {
+            if let Some(Subject::Patch {
+                version: v,
+                index: Some((i, n)),
+                ..
+            }) = messages[message].subject()
+            {
+                let taken = patches.iter().any(|(taken, _)| taken == i);
+
+                if *v == version && *n == total && *i > 0 && *i <= total && !taken {
+                    patches.push((*i, message));
+                }
+            }
+        }
+
+        patches.sort();
+
+        Some(Series {
+            version,
+            total,
+            cover: (first == 0).then_some(root),
+            patches: patches.into_iter().map(|(_, message)| message).collect(),
+        })
+    }
+}
+
+/// `index` followed by the message it replies to, the message that one replies to and so on
+fn ancestors(parents: &[Option<usize>], index: usize) -> impl Iterator<Item = usize> + '_ {
+    std::iter::successors(Some(index), |index| parents[*index])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mailbox::Mailbox;
+
+    #[test]
+    fn thread_sample() {
+        let input = include_str!("samples/thread.mbx");
+        let mailbox = Mailbox::try_from(input).unwrap();
+        let threads = Threads::new(&mailbox.messages);
+
+        // The standalone patch is newer than every message of the series
+        assert_eq!(threads.roots(), [4, 0]);
+        assert_eq!(threads.children(0), [1, 2]);
+        assert_eq!(threads.children(1), [3]);
+        assert_eq!(threads.parent(3), Some(1));
+        assert_eq!(threads.root(3), 0);
+        assert_eq!(threads.depth(3), 2);
+        assert_eq!(threads.order(), [4, 0, 1, 3, 2]);
+
+        let series = threads.series(&mailbox.messages, 0).unwrap();
+        assert_eq!(series.version, Some(2));
+        assert_eq!(series.total, 2);
+        assert_eq!(series.cover, Some(0));
+        assert_eq!(series.patches, [1, 2]);
+
+        let single = threads.series(&mailbox.messages, 4).unwrap();
+        assert_eq!(single.patches, [4]);
+        assert!(threads.series(&mailbox.messages, 3).is_none());
+    }
+
+    #[test]
+    fn thread_cycles() {
+        let input = "From git@z Thu Jan  1 00:00:00 1970\nMessage-Id: <a>\nIn-Reply-To: <b>\n\na\n\
+                     From git@z Thu Jan  1 00:00:00 1970\nMessage-Id: <b>\nIn-Reply-To: <a>\n\nb\n\
+                     From git@z Thu Jan  1 00:00:00 1970\nMessage-Id: <a>\nIn-Reply-To: <a>\n\nc\n";
+        let mailbox = Mailbox::try_from(input).unwrap();
+        let threads = Threads::new(&mailbox.messages);
+
+        assert_eq!(threads.parent(0), Some(1));
+        assert_eq!(threads.parent(1), None);
+        assert_eq!(threads.parent(2), Some(0));
+        assert_eq!(threads.roots(), [1]);
+    }
+}
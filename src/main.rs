@@ -1,59 +1,33 @@
-use std::{
-    io::{stdin, Read},
-    path::Path,
-};
-
 use clap::Parser;
-use cli::Args;
-use color_eyre::eyre::{Context, Result};
-use papr::mailbox::Mailbox;
-use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use cli::{Args, Command};
+use color_eyre::eyre::Result;
 
 pub mod cli;
+pub mod commands;
+pub mod config;
+pub mod pager;
+pub mod select;
 
 fn main() -> Result<()> {
-    let Args { files, frontmatter } = Args::parse();
-
-    // If no files are provided, read from STDIN
-    let files = if files.is_empty() {
-        let mut content = String::new();
-
-        stdin()
-            .read_to_string(&mut content)
-            .with_context(|| "Failed to read input from STDIN")?;
-
-        vec![("STDIN".to_string(), content)]
-    } else {
-        read_all(files).with_context(|| "While opening files")?
-    };
-
-    for (path, content) in files {
-        let mut mailbox = Mailbox::try_from(content.as_str())?;
-
-        if frontmatter {
-            let messages = mailbox.messages;
-            
-            mailbox.messages = messages.into_iter().map(|mut message| {
-                message.body = message.body.front_matter_only();
-                message
-            }).collect();
-        }
-
-        println!("{}:\n{}", path, mailbox);
+    match Args::parse().command() {
+        Command::Show(args) => commands::show::run(args),
+        Command::List(common) => commands::list::run(common),
+        Command::Thread(common) => commands::thread::run(common),
+        Command::Stats(common) => commands::stats::run(common),
+        Command::Lint(args) => commands::lint::run(args),
+        Command::Archive {
+            files,
+            output,
+            title,
+            theme,
+            diff,
+        } => commands::archive::run(files, &output, title, theme, &diff),
+        Command::Split(args) => commands::split::run(args),
+        Command::Merge {
+            files,
+            output,
+            sort,
+            quiet,
+        } => commands::merge::run(files, output.as_deref(), sort, quiet),
     }
-
-    Ok(())
-}
-
-fn read_all(files: Vec<String>) -> Result<Vec<(String, String)>> {
-    files
-        .into_par_iter()
-        .map(|file| {
-            let path = Path::new(&file);
-            let content = std::fs::read_to_string(path)
-                .with_context(|| format!("File {} could not be opened", &path.display()))?;
-
-            Ok((file, content))
-        })
-        .collect()
 }
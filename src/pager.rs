@@ -0,0 +1,157 @@
+use std::{
+    env,
+    io::{self, Write},
+    process::{Command, Stdio},
+};
+
+use color_eyre::eyre::{Context, Result};
+
+/// An external program output is piped through, like `less`
+pub struct Pager {
+    command: String,
+}
+
+impl Pager {
+    /// Finds the pager to use: `$PAPR_PAGER`, then `$PAGER` and finally `less -R`. Setting any of
+    /// them to an empty string or `cat` disables paging
+    pub fn from_env() -> Option<Pager> {
+        let command = env::var("PAPR_PAGER")
+            .or_else(|_| env::var("PAGER"))
+            .unwrap_or_else(|_| "less -R".to_string());
+        let command = command.trim();
+
+        if command.is_empty() || command == "cat" {
+            return None;
+        }
+
+        Some(Pager {
+            command: command.to_string(),
+        })
+    }
+
+    /// Whether the pager is `less`, which understands the extra options papr passes to it
+    pub fn is_less(&self) -> bool {
+        self.command
+            .split_whitespace()
+            .next()
+            .is_some_and(|program| program == "less" || program.ends_with("/less"))
+    }
+
+    /// Pipes `output` through the pager and waits for the user to quit it. `args` are appended to
+    /// the pager command
+    pub fn page(&self, output: &[u8], args: &[String]) -> Result<()> {
+        let mut command = shell(&format!("{} \"$@\"", self.command));
+        command.args(args).stdin(Stdio::piped());
+
+        // Same defaults as git: quit if the output fits the screen, keep colors and don't clear
+        // the screen on exit
+        if env::var_os("LESS").is_none() {
+            command.env("LESS", "FRX");
+        }
+
+        let mut child = command
+            .spawn()
+            .with_context(|| format!("Failed to start pager `{}`", self.command))?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            match stdin.write_all(output) {
+                // The user quit the pager before reading everything
+                Err(e) if e.kind() == io::ErrorKind::BrokenPipe => {}
+                result => result.with_context(|| "Failed to write to the pager")?,
+            }
+        }
+
+        child
+            .wait()
+            .with_context(|| format!("Failed to wait for pager `{}`", self.command))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(unix)]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(command).arg("sh");
+    shell
+}
+
+#[cfg(not(unix))]
+fn shell(command: &str) -> Command {
+    let command = command.trim_end_matches(" \"$@\"");
+    let mut shell = Command::new("cmd");
+    shell.arg("/C").arg(command);
+    shell
+}
+
+/// Whether `output` is taller than the terminal once its long lines wrap, unknown terminal sizes
+/// are considered tall
+pub fn is_taller_than_terminal(output: &[u8]) -> bool {
+    let Some((terminal_size::Width(width), terminal_size::Height(height))) =
+        terminal_size::terminal_size()
+    else {
+        return true;
+    };
+
+    rows(output, usize::from(width)) >= usize::from(height)
+}
+
+/// The number of rows `output` takes in a terminal `width` columns wide. ANSI escape sequences
+/// take no room and tabs go to the next multiple of 8
+fn rows(output: &[u8], width: usize) -> usize {
+    let width = width.max(1);
+
+    String::from_utf8_lossy(output)
+        .lines()
+        .map(|line| {
+            let mut columns = 0usize;
+            let mut chars = line.chars();
+
+            while let Some(c) = chars.next() {
+                match c {
+                    '\x1b' => {
+                        // Skips a CSI sequence like `\x1b[1;31m` up to its final byte
+                        if chars.next() == Some('[') {
+                            for c in chars.by_ref() {
+                                if ('@'..='~').contains(&c) {
+                                    break;
+                                }
+                            }
+                        }
+                    }
+                    '\t' => columns += 8 - columns % 8,
+                    c if c.is_control() => {}
+                    _ => columns += 1,
+                }
+            }
+
+            columns.div_ceil(width).max(1)
+        })
+        .sum()
+}
+
+/// The width of the terminal, or of `$COLUMNS` when the output isn't a terminal, 80 by default
+pub fn terminal_width() -> usize {
+    if let Some((terminal_size::Width(width), _)) = terminal_size::terminal_size() {
+        return usize::from(width);
+    }
+
+    std::env::var("COLUMNS")
+        .ok()
+        .and_then(|columns| columns.parse().ok())
+        .unwrap_or(80)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn count_wrapped_rows() {
+        assert_eq!(rows(b"a\nb\n", 80), 2);
+        assert_eq!(rows(b"\n\n", 80), 2);
+        assert_eq!(rows("x".repeat(81).as_bytes(), 80), 2);
+        assert_eq!(rows(b"\x1b[1;31mabcd\x1b[0m\n", 4), 1);
+        assert_eq!(rows(b"\tab\n", 8), 2);
+    }
+}
@@ -0,0 +1,601 @@
+//! A small language to search messages, like
+//! `from:alice@example.com and subject:"net:" and not has:Reviewed-by and date>2024-01-01`
+//!
+//! ```text
+//! query = and ("or" and)*
+//! and   = not (["and"] not)*
+//! not   = "not" not | "(" query ")" | term
+//! term  = field ":" value | "date" ("=" | "<" | "<=" | ">" | ">=") value | value
+//! value = word | "quoted \"string\""
+//! ```
+//!
+//! `and` binds tighter than `or` and can be left out, `foo bar` is `foo and bar`. Keywords ignore
+//! case. A value without a field is looked for in the subject and the body
+
+use std::{fmt::Display, str::FromStr};
+
+use chrono::{DateTime, Utc};
+use color_eyre::eyre::{eyre, Result};
+
+use crate::{
+    mailbox::message::{header::Subject, Message},
+    utils,
+};
+
+/// The fields a term can look at
+const FIELDS: &str = "from, to, cc, subject, body, id, list, has, is, version, date";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Query {
+    And(Box<Query>, Box<Query>),
+    Or(Box<Query>, Box<Query>),
+    Not(Box<Query>),
+    Term(Term),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Term {
+    /// In the subject or the body, ignoring case
+    Text(String),
+    /// A person, matched like [`Person::matches`](crate::mailbox::message::header::Person::matches)
+    From(String),
+    To(String),
+    Cc(String),
+    /// In the subject, ignoring case
+    Subject(String),
+    /// In the body, ignoring case
+    Body(String),
+    /// The `Message-Id`, without `< >`
+    Id(String),
+    /// In the `List-Id` header, ignoring case
+    List(String),
+    /// A trailer key, ignoring case
+    Has(String),
+    Is(Kind),
+    /// The version of a patch, patches without one are the first version
+    Version(usize),
+    /// A date in `start..end`, any end can be left open
+    Date {
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Patch,
+    Cover,
+    Reply,
+}
+
+impl Query {
+    /// Parses a query, relative dates like `2 weeks ago` are taken from `now`
+    pub fn parse(input: &str, now: DateTime<Utc>) -> Result<Self> {
+        let mut parser = Parser {
+            input,
+            tokens: tokenize(input)?,
+            next: 0,
+            now,
+        };
+
+        if parser.tokens.is_empty() {
+            return Err(error(input, 0, "the query is empty"));
+        }
+
+        let query = parser.or()?;
+
+        match parser.tokens.get(parser.next) {
+            Some(token) => Err(error(input, token.at, "unexpected `)`")),
+            None => Ok(query),
+        }
+    }
+
+    pub fn matches(&self, message: &Message) -> bool {
+        match self {
+            Query::And(left, right) => left.matches(message) && right.matches(message),
+            Query::Or(left, right) => left.matches(message) || right.matches(message),
+            Query::Not(query) => !query.matches(message),
+            Query::Term(term) => term.matches(message),
+        }
+    }
+}
+
+impl FromStr for Query {
+    type Err = color_eyre::Report;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Query::parse(value, Utc::now())
+    }
+}
+
+impl Term {
+    pub fn matches(&self, message: &Message) -> bool {
+        let subject = || {
+            message
+                .subject()
+                .map(|subject| subject.to_string())
+                .unwrap_or_default()
+        };
+
+        match self {
+            Term::Text(text) => {
+                contains(&subject(), text) || contains(&message.body.to_string(), text)
+            }
+            Term::From(pattern) => message.from().is_some_and(|from| from.matches(pattern)),
+            Term::To(pattern) => message.to().iter().any(|person| person.matches(pattern)),
+            Term::Cc(pattern) => message.cc().iter().any(|person| person.matches(pattern)),
+            Term::Subject(text) => contains(&subject(), text),
+            Term::Body(text) => contains(&message.body.to_string(), text),
+            Term::Id(id) => message.message_id() == Some(id),
+            Term::List(text) => message.headers.iter().any(|header| {
+                header.name().eq_ignore_ascii_case("List-Id") && contains(&header.value(), text)
+            }),
+            Term::Has(key) => message
+                .body
+                .trailers()
+                .iter()
+                .any(|(trailer, _)| trailer.eq_ignore_ascii_case(key)),
+            Term::Is(Kind::Patch) => message.is_patch() && !message.is_cover_letter(),
+            Term::Is(Kind::Cover) => message.is_cover_letter(),
+            Term::Is(Kind::Reply) => message.is_reply(),
+            Term::Version(version) => match message.subject() {
+                Some(Subject::Patch { version: v, .. }) => v.unwrap_or(1) == *version,
+                _ => false,
+            },
+            Term::Date { start, end } => message.date().is_some_and(|date| {
+                start.is_none_or(|start| start <= date) && end.is_none_or(|end| date < end)
+            }),
+        }
+    }
+}
+
+fn contains(text: &str, pattern: &str) -> bool {
+    text.to_lowercase().contains(&pattern.to_lowercase())
+}
+
+/// An error pointing at the byte `at` of the query
+fn error(input: &str, at: usize, message: impl Display) -> color_eyre::Report {
+    let column = input[..at].chars().count();
+
+    eyre!(
+        "Invalid query: {}\n  {}\n  {}^",
+        message,
+        input,
+        " ".repeat(column)
+    )
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Colon,
+    Eq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Op {
+    const ALL: [(&'static str, Op); 6] = [
+        (":", Op::Colon),
+        ("<=", Op::Le),
+        (">=", Op::Ge),
+        ("<", Op::Lt),
+        (">", Op::Gt),
+        ("=", Op::Eq),
+    ];
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TokenKind {
+    Open,
+    Close,
+    And,
+    Or,
+    Not,
+    Term {
+        field: Option<(String, Op)>,
+        value: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Token {
+    kind: TokenKind,
+    /// Where the token starts in the query
+    at: usize,
+    /// Where the value of a term starts
+    value_at: usize,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut at = 0;
+
+    while let Some(c) = input[at..].chars().next() {
+        let token = |kind, value_at| Token { kind, at, value_at };
+
+        if c.is_whitespace() {
+            at += c.len_utf8();
+        } else if c == '(' || c == ')' {
+            let kind = if c == '(' {
+                TokenKind::Open
+            } else {
+                TokenKind::Close
+            };
+            tokens.push(token(kind, at));
+            at += 1;
+        } else {
+            let rest = &input[at..];
+            let name = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == '_'))
+                .unwrap_or(rest.len());
+            let field = Op::ALL
+                .iter()
+                .find(|(op, _)| name > 0 && rest[name..].starts_with(op))
+                .map(|(op, kind)| (rest[..name].to_string(), *kind, name + op.len()));
+
+            let value_at = at + field.as_ref().map_or(0, |(_, _, len)| *len);
+            let (value, quoted, end) = value(input, value_at)?;
+
+            let kind = match field {
+                Some((name, _, _)) if value.is_empty() && !quoted => {
+                    let op = &input[at + name.len()..value_at];
+                    return Err(error(
+                        input,
+                        value_at,
+                        format!("expected a value after `{}{}`", name, op),
+                    ));
+                }
+                Some((name, op, _)) => TokenKind::Term {
+                    field: Some((name, op)),
+                    value,
+                },
+                None if quoted => TokenKind::Term { field: None, value },
+                None => match value.to_ascii_lowercase().as_str() {
+                    "and" => TokenKind::And,
+                    "or" => TokenKind::Or,
+                    "not" => TokenKind::Not,
+                    _ => TokenKind::Term { field: None, value },
+                },
+            };
+
+            tokens.push(token(kind, value_at));
+            at = end;
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// The value starting at `at`, whether it was quoted, and where it ends. Unquoted values stop at
+/// a space or a parenthesis, quoted ones can escape `"` and `\` with a `\`
+fn value(input: &str, at: usize) -> Result<(String, bool, usize)> {
+    let rest = &input[at..];
+
+    let Some(quoted) = rest.strip_prefix('"') else {
+        let end = rest
+            .find(|c: char| c.is_whitespace() || c == '(' || c == ')')
+            .unwrap_or(rest.len());
+        return Ok((rest[..end].to_string(), false, at + end));
+    };
+
+    let mut value = String::new();
+    let mut chars = quoted.char_indices();
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Ok((value, true, at + 1 + i + 1)),
+            '\\' => match chars.next() {
+                Some((_, c)) => value.push(c),
+                None => break,
+            },
+            c => value.push(c),
+        }
+    }
+
+    Err(error(input, at, "this quote is never closed"))
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    tokens: Vec<Token>,
+    next: usize,
+    now: DateTime<Utc>,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&TokenKind> {
+        self.tokens.get(self.next).map(|token| &token.kind)
+    }
+
+    fn eat(&mut self, kind: TokenKind) -> bool {
+        let found = self.peek() == Some(&kind);
+        self.next += found as usize;
+        found
+    }
+
+    fn or(&mut self) -> Result<Query> {
+        let mut query = self.and()?;
+
+        while self.eat(TokenKind::Or) {
+            query = Query::Or(Box::new(query), Box::new(self.and()?));
+        }
+
+        Ok(query)
+    }
+
+    fn and(&mut self) -> Result<Query> {
+        let mut query = self.not()?;
+
+        loop {
+            let implicit = matches!(
+                self.peek(),
+                Some(TokenKind::Open | TokenKind::Not | TokenKind::Term { .. })
+            );
+
+            if !(self.eat(TokenKind::And) || implicit) {
+                return Ok(query);
+            }
+
+            query = Query::And(Box::new(query), Box::new(self.not()?));
+        }
+    }
+
+    fn not(&mut self) -> Result<Query> {
+        if self.eat(TokenKind::Not) {
+            return Ok(Query::Not(Box::new(self.not()?)));
+        }
+
+        let Some(token) = self.tokens.get(self.next).cloned() else {
+            let last = self.tokens.last().map_or("", |token| match token.kind {
+                TokenKind::And => "and",
+                TokenKind::Or => "or",
+                TokenKind::Not => "not",
+                _ => "(",
+            });
+            return Err(error(
+                self.input,
+                self.input.len(),
+                format!("expected a term after `{}`", last),
+            ));
+        };
+
+        self.next += 1;
+
+        match token.kind {
+            TokenKind::Open => {
+                let query = self.or()?;
+
+                if !self.eat(TokenKind::Close) {
+                    return Err(error(self.input, token.at, "this `(` is never closed"));
+                }
+
+                Ok(query)
+            }
+            TokenKind::Term {
+                ref field,
+                ref value,
+            } => self
+                .term(&token, field.clone(), value.clone())
+                .map(Query::Term),
+            TokenKind::Close => Err(error(self.input, token.at, "expected a term before `)`")),
+            TokenKind::And | TokenKind::Or => Err(error(
+                self.input,
+                token.at,
+                "expected a term before this operator",
+            )),
+            TokenKind::Not => unreachable!("`not` was eaten above"),
+        }
+    }
+
+    fn term(&self, token: &Token, field: Option<(String, Op)>, value: String) -> Result<Term> {
+        let Some((name, op)) = field else {
+            return Ok(Term::Text(value));
+        };
+
+        let name = name.to_ascii_lowercase();
+
+        if name == "date" {
+            let (start, end) = utils::parse_date(&value, self.now)
+                .map_err(|e| error(self.input, token.value_at, e))?;
+
+            return Ok(match op {
+                Op::Colon | Op::Eq => Term::Date {
+                    start: Some(start),
+                    end: Some(end),
+                },
+                Op::Lt => Term::Date {
+                    start: None,
+                    end: Some(start),
+                },
+                Op::Le => Term::Date {
+                    start: None,
+                    end: Some(end),
+                },
+                Op::Gt => Term::Date {
+                    start: Some(end),
+                    end: None,
+                },
+                Op::Ge => Term::Date {
+                    start: Some(start),
+                    end: None,
+                },
+            });
+        }
+
+        if op != Op::Colon {
+            let at = token.at + name.len();
+            return Err(error(
+                self.input,
+                at,
+                format!("only dates can be compared, expected `{}:`", name),
+            ));
+        }
+
+        Ok(match name.as_str() {
+            "from" => Term::From(value),
+            "to" => Term::To(value),
+            "cc" => Term::Cc(value),
+            "subject" => Term::Subject(value),
+            "body" => Term::Body(value),
+            "id" => Term::Id(value.trim_matches(|c| c == '<' || c == '>').to_string()),
+            "list" => Term::List(value),
+            "has" => Term::Has(value),
+            "is" => Term::Is(match value.to_ascii_lowercase().as_str() {
+                "patch" => Kind::Patch,
+                "cover" => Kind::Cover,
+                "reply" => Kind::Reply,
+                _ => {
+                    return Err(error(
+                        self.input,
+                        token.value_at,
+                        format!("unknown kind `{}`, expected patch, cover or reply", value),
+                    ))
+                }
+            }),
+            "version" => Term::Version(
+                value
+                    .strip_prefix(['v', 'V'])
+                    .unwrap_or(&value)
+                    .parse()
+                    .map_err(|_| {
+                        error(
+                            self.input,
+                            token.value_at,
+                            format!("invalid version `{}`, expected a number like 2", value),
+                        )
+                    })?,
+            ),
+            _ => {
+                return Err(error(
+                    self.input,
+                    token.at,
+                    format!("unknown field `{}`, expected one of {}", name, FIELDS),
+                ))
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mailbox::Mailbox;
+    use chrono::TimeZone;
+
+    fn parse(input: &str) -> Result<Query> {
+        Query::parse(input, Utc.with_ymd_and_hms(2024, 6, 10, 0, 0, 0).unwrap())
+    }
+
+    fn term(term: Term) -> Box<Query> {
+        Box::new(Query::Term(term))
+    }
+
+    #[test]
+    fn parse_queries() {
+        assert_eq!(
+            parse("from:alice subject:\"net: \\\"fix\\\"\"").unwrap(),
+            Query::And(
+                term(Term::From("alice".to_string())),
+                term(Term::Subject("net: \"fix\"".to_string()))
+            )
+        );
+
+        // `and` binds tighter than `or`
+        assert_eq!(
+            parse("foo OR bar and NOT has:Acked-by").unwrap(),
+            Query::Or(
+                term(Term::Text("foo".to_string())),
+                Box::new(Query::And(
+                    term(Term::Text("bar".to_string())),
+                    Box::new(Query::Not(term(Term::Has("Acked-by".to_string()))))
+                ))
+            )
+        );
+
+        assert_eq!(
+            parse("(foo or bar) \"and\"").unwrap(),
+            Query::And(
+                Box::new(Query::Or(
+                    term(Term::Text("foo".to_string())),
+                    term(Term::Text("bar".to_string()))
+                )),
+                term(Term::Text("and".to_string()))
+            )
+        );
+
+        assert_eq!(
+            parse("date>2024-01-01").unwrap(),
+            Query::Term(Term::Date {
+                start: Some(Utc.with_ymd_and_hms(2024, 1, 2, 0, 0, 0).unwrap()),
+                end: None
+            })
+        );
+        assert_eq!(
+            parse("id:<a@b> version:v2").unwrap(),
+            Query::And(term(Term::Id("a@b".to_string())), term(Term::Version(2)))
+        );
+    }
+
+    #[test]
+    fn query_errors() {
+        let message = |input| parse(input).unwrap_err().to_string();
+
+        assert_eq!(
+            message("from:alice and form:bob"),
+            format!(
+                "Invalid query: unknown field `form`, expected one of {}\n  from:alice and form:bob\n                 ^",
+                FIELDS
+            )
+        );
+        assert_eq!(
+            message("subject:\"net"),
+            "Invalid query: this quote is never closed\n  subject:\"net\n          ^"
+        );
+        assert_eq!(
+            message("(foo or"),
+            "Invalid query: expected a term after `or`\n  (foo or\n         ^"
+        );
+        assert_eq!(
+            message("(foo"),
+            "Invalid query: this `(` is never closed\n  (foo\n  ^"
+        );
+        assert!(message("foo)").contains("unexpected `)`"));
+        assert!(message("from: alice").contains("expected a value after `from:`"));
+        assert!(message("subject>net").contains("only dates can be compared"));
+        assert!(message("date<someday").contains("Invalid date `someday`"));
+        assert!(message("is:thing").contains("unknown kind `thing`"));
+        assert!(message("and foo").contains("expected a term before this operator"));
+        assert!(message("  ").contains("the query is empty"));
+    }
+
+    #[test]
+    fn match_queries() {
+        let input = include_str!("mailbox/samples/thread.mbx");
+        let mailbox = Mailbox::try_from(input).unwrap();
+        let indexes = |query: &str| {
+            let query = parse(query).unwrap();
+
+            mailbox
+                .messages
+                .iter()
+                .enumerate()
+                .filter(|(_, message)| query.matches(message))
+                .map(|(index, _)| index)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            indexes(
+                "from:alice@example.com and subject:\"net:\" and not has:Reviewed-by and date>2024-01-01"
+            ),
+            [0, 1, 2]
+        );
+        assert_eq!(indexes("is:patch list:netdev"), [1, 2]);
+        assert_eq!(indexes("is:cover or is:reply"), [0, 3]);
+        assert_eq!(indexes("date>=2024-06-04 not to:linux-mm"), [3]);
+        assert_eq!(indexes("has:reviewed-by or baz version:1"), [3, 4]);
+        assert_eq!(indexes("id:ZmBob-review@kernel.org"), [3]);
+    }
+}
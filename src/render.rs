@@ -0,0 +1,585 @@
+use std::{borrow::Cow, io, str::FromStr};
+
+use color_eyre::eyre::eyre;
+
+use crate::{
+    diff::{stat::Stat, words::Tokenizer, Patch},
+    mailbox::{
+        message::{
+            body::Body,
+            header::{Header, Person, Subject},
+            Message,
+        },
+        Mailbox,
+    },
+    whitespace::Rules,
+};
+
+pub mod ansi;
+pub mod html;
+mod patch;
+pub mod plain;
+mod stat;
+pub mod style;
+pub mod theme;
+
+/// The semantic role a piece of text plays in the output. Backends decide how each role looks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Role {
+    /// Text without any special meaning
+    Text,
+    /// The name of the file (or STDIN) a mailbox was read from
+    Source,
+    /// The key of a header without special meaning
+    HeaderKey,
+    /// The key of the `From` header
+    FromKey,
+    /// The key of the `Date` header
+    DateKey,
+    /// The key of the `Author` header
+    AuthorKey,
+    /// The key of the `Subject` header
+    SubjectKey,
+    PersonName,
+    Email,
+    Date,
+    /// The `[PATCH` and `]` around the version and index of a patch subject
+    PatchPrefix,
+    /// The `vN` of a patch subject
+    PatchVersion,
+    /// The `i/N` of a patch subject
+    PatchIndex,
+    /// The `tag:` prefixes of a subject
+    Tags,
+    /// The `---` lines splitting the sections of a body
+    Separator,
+    /// The key of a trailer without special meaning
+    Trailer,
+    /// The key of a `Signed-off-by` or `Co-developed-by` trailer
+    SignOff,
+    /// The key of a `Reviewed-by`, `Acked-by` or `Tested-by` trailer
+    Review,
+    /// The `diff --git`, `index`, `---` and `+++` lines of a file diff
+    DiffHeader,
+    /// The `@@ -a,b +c,d @@` line starting a hunk
+    HunkHeader,
+    /// The number of a line of a hunk in the old or the new file
+    LineNumber,
+    /// A line added by a diff, also the `+` bars of a diffstat
+    Added,
+    /// A line removed by a diff, also the `-` bars of a diffstat
+    Removed,
+    /// The words changed by an added line, compared to the removed line it replaces
+    AddedWord,
+    /// The words changed in a removed line, compared to the added line replacing it
+    RemovedWord,
+    /// A line of context in a hunk
+    Context,
+    /// A whitespace error in an added line, like trailing whitespace
+    Whitespace,
+    /// The `-- ` signature closing a message
+    Signature,
+    /// A keyword of the language of the code in a diff
+    Keyword,
+    /// A type of the language of the code in a diff
+    Type,
+    /// A string or character literal in a diff
+    String,
+    /// A number literal in a diff
+    Number,
+    /// A comment in a diff
+    Comment,
+    /// A preprocessor directive, attribute, macro or variable expansion in a diff
+    Meta,
+}
+
+impl Role {
+    pub const ALL: [Role; 34] = [
+        Role::Text,
+        Role::Source,
+        Role::HeaderKey,
+        Role::FromKey,
+        Role::DateKey,
+        Role::AuthorKey,
+        Role::SubjectKey,
+        Role::PersonName,
+        Role::Email,
+        Role::Date,
+        Role::PatchPrefix,
+        Role::PatchVersion,
+        Role::PatchIndex,
+        Role::Tags,
+        Role::Separator,
+        Role::Trailer,
+        Role::SignOff,
+        Role::Review,
+        Role::DiffHeader,
+        Role::HunkHeader,
+        Role::LineNumber,
+        Role::Added,
+        Role::Removed,
+        Role::AddedWord,
+        Role::RemovedWord,
+        Role::Context,
+        Role::Whitespace,
+        Role::Signature,
+        Role::Keyword,
+        Role::Type,
+        Role::String,
+        Role::Number,
+        Role::Comment,
+        Role::Meta,
+    ];
+
+    /// The name used to refer to the role in configuration files
+    pub fn name(self) -> &'static str {
+        match self {
+            Role::Text => "text",
+            Role::Source => "source",
+            Role::HeaderKey => "header_key",
+            Role::FromKey => "from_key",
+            Role::DateKey => "date_key",
+            Role::AuthorKey => "author_key",
+            Role::SubjectKey => "subject_key",
+            Role::PersonName => "person_name",
+            Role::Email => "email",
+            Role::Date => "date",
+            Role::PatchPrefix => "patch_prefix",
+            Role::PatchVersion => "patch_version",
+            Role::PatchIndex => "patch_index",
+            Role::Tags => "tags",
+            Role::Separator => "separator",
+            Role::Trailer => "trailer",
+            Role::SignOff => "sign_off",
+            Role::Review => "review",
+            Role::DiffHeader => "diff_header",
+            Role::HunkHeader => "hunk_header",
+            Role::LineNumber => "line_number",
+            Role::Added => "added",
+            Role::Removed => "removed",
+            Role::AddedWord => "added_word",
+            Role::RemovedWord => "removed_word",
+            Role::Context => "context",
+            Role::Whitespace => "whitespace",
+            Role::Signature => "signature",
+            Role::Keyword => "keyword",
+            Role::Type => "type",
+            Role::String => "string",
+            Role::Number => "number",
+            Role::Comment => "comment",
+            Role::Meta => "meta",
+        }
+    }
+}
+
+impl FromStr for Role {
+    type Err = color_eyre::Report;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Role::ALL
+            .into_iter()
+            .find(|role| role.name() == value)
+            .ok_or(eyre!("Unknown role `{}`", value))
+    }
+}
+
+/// A piece of text tagged with the role it plays
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Span<'a> {
+    pub role: Role,
+    /// The role of the text in the code of a diff, like [`Role::Keyword`], styled over `role`
+    pub syntax: Option<Role>,
+    pub text: Cow<'a, str>,
+}
+
+impl<'a> Span<'a> {
+    pub fn new(role: Role, text: impl Into<Cow<'a, str>>) -> Self {
+        Span {
+            role,
+            syntax: None,
+            text: text.into(),
+        }
+    }
+
+    pub fn with_syntax(self, syntax: Option<Role>) -> Self {
+        Span { syntax, ..self }
+    }
+
+    pub fn text(text: impl Into<Cow<'a, str>>) -> Self {
+        Span::new(Role::Text, text)
+    }
+}
+
+/// An output format. The renderer walks a mailbox and feeds the backend line by line
+pub trait Backend {
+    /// Called before the messages of a mailbox with the name of the source it was read from
+    fn begin_mailbox(&mut self, source: &str) -> io::Result<()> {
+        self.line(&[Span::new(Role::Source, format!("{}:", source))])
+    }
+
+    /// Called before the lines of the `index`-th message of the current mailbox
+    fn begin_message(&mut self, _index: usize, _message: &Message) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Called before the lines of each file changed by a patch
+    fn begin_file(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Outputs a single line made of `spans`
+    fn line(&mut self, spans: &[Span]) -> io::Result<()>;
+
+    /// Called after the lines of a message
+    fn end_message(&mut self) -> io::Result<()> {
+        self.line(&[])
+    }
+
+    /// Called once everything was rendered
+    fn finish(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// What the renderer highlights beyond the role of each line
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// The whitespace errors highlighted in the lines added by patches
+    pub whitespace: Rules,
+    /// How a removed line and the added line replacing it are compared to emphasize the words
+    /// that changed, `None` to not compare them
+    pub word_diff: Option<Tokenizer>,
+    /// Whether the code in diffs is highlighted according to the language of its file
+    pub syntax: bool,
+    /// The width in which hunks are rendered in two columns, the old lines facing the new ones.
+    /// `None`, or a width too narrow for two columns, renders them unified
+    pub side_by_side: Option<usize>,
+    /// Whether the lines of unified hunks start with their numbers in the old and the new file
+    pub line_numbers: bool,
+    /// The width of the diffstat replacing the diff of patches, `None` to render the diff
+    pub diffstat: Option<usize>,
+    /// Whether the encoded payloads of binary patches are shown, instead of a line summarizing
+    /// them
+    pub binary: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            whitespace: Rules::default(),
+            word_diff: Some(Tokenizer::Word),
+            syntax: true,
+            side_by_side: None,
+            line_numbers: false,
+            diffstat: None,
+            binary: false,
+        }
+    }
+}
+
+/// Renders every message of `mailbox` into `backend`
+pub fn mailbox(
+    source: &str,
+    mailbox: &Mailbox,
+    options: &Options,
+    backend: &mut (impl Backend + ?Sized),
+) -> io::Result<()> {
+    backend.begin_mailbox(source)?;
+
+    for (index, message) in mailbox.messages.iter().enumerate() {
+        backend.begin_message(index, message)?;
+        self::message(message, options, backend)?;
+        backend.end_message()?;
+    }
+
+    Ok(())
+}
+
+/// Renders the diffstat of all the patches of `mailbox` together into `backend`, in `width`
+/// columns
+pub fn series_stat(
+    source: &str,
+    mailbox: &Mailbox,
+    width: usize,
+    backend: &mut (impl Backend + ?Sized),
+) -> io::Result<()> {
+    let mut stat = Stat::default();
+
+    for message in &mailbox.messages {
+        if let Body::WithFrontMatter { body, .. } = &message.body {
+            stat.add(&Stat::of(&Patch::parse(body)));
+        }
+    }
+
+    backend.begin_mailbox(source)?;
+
+    for line in stat::lines(&stat, width) {
+        backend.line(&line)?;
+    }
+
+    backend.line(&[])
+}
+
+/// Renders the headers and the body of a single message into `backend`
+pub fn message(
+    message: &Message,
+    options: &Options,
+    backend: &mut (impl Backend + ?Sized),
+) -> io::Result<()> {
+    for header in &message.headers {
+        backend.line(&header_spans(header))?;
+    }
+
+    backend.line(&[])?;
+
+    for line in body_lines(&message.body, options) {
+        if line
+            .first()
+            .is_some_and(|span| span.role == Role::DiffHeader && span.text.starts_with("diff "))
+        {
+            backend.begin_file()?;
+        }
+
+        backend.line(&line)?;
+    }
+
+    Ok(())
+}
+
+pub fn header_spans<'a>(header: &'a Header) -> Vec<Span<'a>> {
+    let (key, mut value) = match header {
+        Header::From(person) => (Span::new(Role::FromKey, "From:"), person_spans(person)),
+        Header::Date(date) => (
+            Span::new(Role::DateKey, "Date:"),
+            vec![Span::new(Role::Date, date.to_rfc2822())],
+        ),
+        Header::Author(person) => (Span::new(Role::AuthorKey, "Author:"), person_spans(person)),
+        Header::Subject(subject) => (
+            Span::new(Role::SubjectKey, "Subject:"),
+            subject_spans(subject),
+        ),
+        Header::MessageId(id) => (
+            Span::new(Role::HeaderKey, "Message-Id:"),
+            vec![Span::text(format!("<{}>", id))],
+        ),
+        Header::InReplyTo(id) => (
+            Span::new(Role::HeaderKey, "In-Reply-To:"),
+            vec![Span::text(format!("<{}>", id))],
+        ),
+        Header::References(ids) => (
+            Span::new(Role::HeaderKey, "References:"),
+            vec![Span::text(
+                ids.iter()
+                    .map(|id| format!("<{}>", id))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            )],
+        ),
+        Header::To(people) | Header::Cc(people) => (
+            Span::new(Role::HeaderKey, format!("{}:", header.name())),
+            people
+                .iter()
+                .enumerate()
+                .flat_map(|(i, person)| {
+                    let separator = (i > 0).then(|| Span::text(", "));
+                    separator.into_iter().chain(person_spans(person))
+                })
+                .collect(),
+        ),
+        Header::Other(key, value) => (
+            Span::new(Role::HeaderKey, format!("{}:", key)),
+            vec![Span::text(unfold(value))],
+        ),
+    };
+
+    let mut spans = vec![key, Span::text(" ")];
+    spans.append(&mut value);
+    spans
+}
+
+pub fn person_spans<'a>(person: &Person<'a>) -> Vec<Span<'a>> {
+    let mut spans = Vec::with_capacity(4);
+
+    if let Some(name) = person.name {
+        spans.push(Span::new(Role::PersonName, name));
+        spans.push(Span::text(" "));
+    }
+
+    spans.push(Span::text("<"));
+    spans.push(Span::new(Role::Email, person.email.to_string()));
+    spans.push(Span::text(">"));
+    spans
+}
+
+pub fn subject_spans<'a>(subject: &'a Subject) -> Vec<Span<'a>> {
+    match subject {
+        Subject::Simple(description) => vec![Span::text(unfold(description))],
+        Subject::Tagged { tags, description } => vec![
+            Span::new(Role::Tags, format!("{}:", tags.join(": "))),
+            Span::text(" "),
+            Span::text(unfold(description)),
+        ],
+        Subject::Patch {
+            version,
+            index,
+            tags,
+            description,
+        } => {
+            let mut spans = vec![Span::new(Role::PatchPrefix, "[PATCH")];
+
+            if let Some(version) = version {
+                spans.push(Span::text(" "));
+                spans.push(Span::new(Role::PatchVersion, format!("v{}", version)));
+            }
+
+            if let Some((index, total)) = index {
+                spans.push(Span::text(" "));
+                spans.push(Span::new(Role::PatchIndex, format!("{}/{}", index, total)));
+            }
+
+            spans.push(Span::new(Role::PatchPrefix, "]"));
+            spans.push(Span::text(" "));
+
+            if !tags.is_empty() {
+                spans.push(Span::new(Role::Tags, format!("{}:", tags.join(": "))));
+                spans.push(Span::text(" "));
+            }
+
+            spans.push(Span::text(unfold(description)));
+            spans
+        }
+    }
+}
+
+/// Joins the lines of a folded header value in a single line
+pub fn unfold(value: &str) -> Cow<'_, str> {
+    if !value.contains('\n') {
+        return Cow::Borrowed(value);
+    }
+
+    Cow::Owned(
+        value
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>()
+            .join(" "),
+    )
+}
+
+pub fn body_lines<'a>(body: &'a Body, options: &Options) -> Vec<Vec<Span<'a>>> {
+    let text_lines = |text: &'a str| text.lines().map(|line| vec![Span::text(line)]);
+    let footer_lines = |footers: &'a [(&'a str, &'a str)]| {
+        footers.iter().map(|(key, value)| trailer_spans(key, value))
+    };
+    let separator = || vec![Span::new(Role::Separator, "---")];
+
+    match body {
+        Body::Simple(body) => text_lines(body).collect(),
+        Body::WithFrontMatter {
+            front_matter,
+            footers,
+            body,
+        } => text_lines(front_matter)
+            .chain([separator()])
+            .chain(footer_lines(footers))
+            .chain([separator()])
+            .chain(match options.diffstat {
+                Some(width) => stat::lines(&Stat::of(&Patch::parse(body)), width),
+                None => patch::lines(body, options),
+            })
+            .collect(),
+        Body::OnlyFrontMatter {
+            front_matter,
+            footers,
+        } => text_lines(front_matter)
+            .chain([separator()])
+            .chain(footer_lines(footers))
+            .collect(),
+    }
+}
+
+/// The role of the key of a trailer: sign-offs, reviews or anything else
+pub fn trailer_role(key: &str) -> Role {
+    match key.to_ascii_lowercase().as_str() {
+        "signed-off-by" | "co-developed-by" => Role::SignOff,
+        "reviewed-by" | "acked-by" | "tested-by" => Role::Review,
+        _ => Role::Trailer,
+    }
+}
+
+pub fn trailer_spans<'a>(key: &'a str, value: &'a str) -> Vec<Span<'a>> {
+    let mut spans = vec![
+        Span::new(trailer_role(key), format!("{}:", key)),
+        Span::text(" "),
+    ];
+
+    match Person::try_from(value) {
+        Ok(person) if person.name.is_some() => spans.append(&mut person_spans(&person)),
+        _ => spans.push(Span::text(value)),
+    }
+
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_patch_subject() {
+        let subject = Subject::try_from("[PATCH v1 1/10] patch-tree: foo message").unwrap();
+        let spans = subject_spans(&subject);
+
+        assert_eq!(
+            spans.iter().map(|s| s.text.as_ref()).collect::<String>(),
+            subject.to_string()
+        );
+        assert!(spans.contains(&Span::new(Role::PatchVersion, "v1")));
+        assert!(spans.contains(&Span::new(Role::PatchIndex, "1/10")));
+        assert!(spans.contains(&Span::new(Role::Tags, "patch-tree:")));
+    }
+
+    #[test]
+    fn render_header() {
+        let header = Header::try_from(("From", "Foo Bar <foo@bar.com>")).unwrap();
+        let spans = header_spans(&header);
+
+        assert_eq!(
+            spans.iter().map(|s| s.text.as_ref()).collect::<String>(),
+            header.to_string()
+        );
+        assert_eq!(spans[0], Span::new(Role::FromKey, "From:"));
+        assert!(spans.contains(&Span::new(Role::PersonName, "Foo Bar")));
+        assert!(spans.contains(&Span::new(Role::Email, "foo@bar.com")));
+    }
+
+    #[test]
+    fn render_patch_lines() {
+        let input = include_str!("mailbox/samples/multi_patches.mbx");
+        let mailbox = Mailbox::try_from(input).unwrap();
+        let lines = body_lines(&mailbox.messages[0].body, &Options::default());
+
+        assert!(lines.contains(&vec![
+            Span::text("test/file.c | 69 "),
+            Span::new(Role::Added, "+".repeat(57)),
+            Span::new(Role::Removed, ""),
+        ]));
+        assert!(lines.ends_with(&[
+            vec![Span::new(Role::Signature, "--")],
+            vec![Span::new(Role::Signature, "1.1.1")],
+        ]));
+        assert!(lines.contains(&vec![
+            Span::new(Role::SignOff, "Signed-off-by:"),
+            Span::text(" "),
+            Span::text("John Doe <john.doe(a)email.com>"),
+        ]));
+    }
+
+    #[test]
+    fn role_names() {
+        for role in Role::ALL {
+            assert_eq!(role.name().parse::<Role>().unwrap(), role);
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use std::io::{self, Write};
+
+use crate::mailbox::message::Message;
+
+use super::{
+    style::{Color, Style},
+    theme::Theme,
+    Backend, Span,
+};
+
+/// Prefix of the first line of each message when search marks are enabled
+pub const MESSAGE_MARK: &str = "\u{25b6} ";
+/// Prefix of the first line of each changed file when search marks are enabled
+pub const FILE_MARK: &str = "\u{394} ";
+/// A regex matching the lines that carry a search mark, meant for `less --pattern`
+pub const MARKS_PATTERN: &str = "^(\u{25b6}|\u{394}) ";
+
+/// Renders spans to a terminal using ANSI escape sequences
+pub struct Ansi<W: Write> {
+    out: W,
+    theme: Theme,
+    marks: bool,
+    pending_mark: Option<&'static str>,
+}
+
+impl<W: Write> Ansi<W> {
+    pub fn new(out: W, theme: Theme) -> Self {
+        Ansi {
+            out,
+            theme,
+            marks: false,
+            pending_mark: None,
+        }
+    }
+
+    /// Prefixes the first line of every message and changed file with a mark that pagers can
+    /// search for (see [`MARKS_PATTERN`]), so jumping between them is a single keystroke
+    pub fn with_marks(self, marks: bool) -> Self {
+        Ansi { marks, ..self }
+    }
+
+    fn mark(&mut self, mark: &'static str) {
+        if self.marks {
+            self.pending_mark = Some(mark);
+        }
+    }
+}
+
+impl<W: Write> Backend for Ansi<W> {
+    fn begin_message(&mut self, _index: usize, _message: &Message) -> io::Result<()> {
+        self.mark(MESSAGE_MARK);
+        Ok(())
+    }
+
+    fn begin_file(&mut self) -> io::Result<()> {
+        self.mark(FILE_MARK);
+        Ok(())
+    }
+
+    fn line(&mut self, spans: &[Span]) -> io::Result<()> {
+        // The mark is written unstyled so it is the very first thing in the line
+        if let Some(mark) = self.pending_mark.take() {
+            write!(self.out, "{}", mark)?;
+        }
+
+        for span in spans {
+            let mut style = self.theme.style(span.role);
+
+            if let Some(syntax) = span.syntax {
+                style = style.layer(self.theme.style(syntax));
+            }
+
+            write_styled(&mut self.out, &style, &span.text)?;
+        }
+
+        writeln!(self.out)
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+/// Writes `text` surrounded by the escape sequences that apply `style`
+pub fn write_styled(out: &mut impl Write, style: &Style, text: &str) -> io::Result<()> {
+    if style.is_plain() || text.is_empty() {
+        return write!(out, "{}", text);
+    }
+
+    write!(out, "\x1b[{}m{}\x1b[0m", sgr(style), text)
+}
+
+/// The Select Graphic Rendition parameters for `style`
+fn sgr(style: &Style) -> String {
+    let mut codes = Vec::new();
+
+    if style.bold {
+        codes.push("1".to_string());
+    }
+    if style.dim {
+        codes.push("2".to_string());
+    }
+    if style.italic {
+        codes.push("3".to_string());
+    }
+    if style.underline {
+        codes.push("4".to_string());
+    }
+    if let Some(fg) = style.fg {
+        codes.push(color_code(fg, 30));
+    }
+    if let Some(bg) = style.bg {
+        codes.push(color_code(bg, 40));
+    }
+
+    codes.join(";")
+}
+
+/// The SGR parameter for `color`, `base` is 30 for foreground and 40 for background colors
+fn color_code(color: Color, base: u8) -> String {
+    match color {
+        Color::Black => base.to_string(),
+        Color::Red => (base + 1).to_string(),
+        Color::Green => (base + 2).to_string(),
+        Color::Yellow => (base + 3).to_string(),
+        Color::Blue => (base + 4).to_string(),
+        Color::Magenta => (base + 5).to_string(),
+        Color::Cyan => (base + 6).to_string(),
+        Color::White => (base + 7).to_string(),
+        Color::Fixed(n) => format!("{};5;{}", base + 8, n),
+        Color::Rgb(r, g, b) => format!("{};2;{};{};{}", base + 8, r, g, b),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        mailbox::{message::header::Header, Mailbox},
+        render::{self, header_spans},
+    };
+
+    #[test]
+    fn ansi_header() {
+        let header = Header::Other("SomeHeader", "SomeValue");
+        let mut ansi = Ansi::new(Vec::new(), Theme::dark());
+        ansi.line(&header_spans(&header)).unwrap();
+
+        assert_eq!(
+            String::from_utf8(ansi.out).unwrap(),
+            "\x1b[36mSomeHeader:\x1b[0m SomeValue\n"
+        );
+    }
+
+    #[test]
+    fn ansi_marks() {
+        let input = include_str!("../mailbox/samples/multi_patches.mbx");
+        let mailbox = Mailbox::try_from(input).unwrap();
+        let mut ansi = Ansi::new(Vec::new(), Theme::monochrome()).with_marks(true);
+        render::mailbox(
+            "multi_patches.mbx",
+            &mailbox,
+            &render::Options::default(),
+            &mut ansi,
+        )
+        .unwrap();
+
+        let output = String::from_utf8(ansi.out).unwrap();
+        let marked = output
+            .lines()
+            .filter(|line| line.starts_with(MESSAGE_MARK))
+            .count();
+        assert_eq!(marked, mailbox.messages.len());
+    }
+
+    #[test]
+    fn ansi_colors() {
+        let style = Style::new().bold().fg(Color::Fixed(208)).bg(Color::Rgb(1, 2, 3));
+        assert_eq!(sgr(&style), "1;38;5;208;48;2;1;2;3");
+    }
+}
@@ -0,0 +1,361 @@
+use std::{
+    collections::HashSet,
+    fmt::Write as _,
+    io::{self, Write},
+};
+
+use crate::mailbox::message::Message;
+
+use super::{
+    style::{Color, Style},
+    theme::Theme,
+    Backend, Role, Span,
+};
+
+/// Renders spans as a self-contained HTML page. Each role becomes a CSS class (`papr-<role>`)
+/// styled by a stylesheet generated from the theme, and each message gets an anchor so links can
+/// point at it
+pub struct Html<W: Write> {
+    out: W,
+    theme: Theme,
+    started: bool,
+    in_message: bool,
+    mailboxes: usize,
+    anchors: HashSet<String>,
+}
+
+impl<W: Write> Html<W> {
+    pub fn new(out: W, theme: Theme) -> Self {
+        Html {
+            out,
+            theme,
+            started: false,
+            in_message: false,
+            mailboxes: 0,
+            anchors: HashSet::new(),
+        }
+    }
+
+    /// Writes the head of the page before the first element
+    fn start(&mut self) -> io::Result<()> {
+        if self.started {
+            return Ok(());
+        }
+
+        self.started = true;
+
+        writeln!(self.out, "<!DOCTYPE html>")?;
+        writeln!(self.out, "<html>")?;
+        writeln!(self.out, "<head>")?;
+        writeln!(self.out, "<meta charset=\"utf-8\">")?;
+        writeln!(self.out, "<title>papr</title>")?;
+        writeln!(self.out, "<style>\n{}</style>", stylesheet(&self.theme))?;
+        writeln!(self.out, "</head>")?;
+        writeln!(self.out, "<body class=\"papr\">")
+    }
+
+    /// A unique anchor for `message`, based on its `Message-Id` when it has one
+    fn anchor(&mut self, index: usize, message: &Message) -> String {
+        let base = message
+            .message_id()
+            .map(slug)
+            .filter(|id| !id.is_empty())
+            .unwrap_or_else(|| format!("message-{}-{}", self.mailboxes, index + 1));
+
+        let mut anchor = base.clone();
+        let mut n = 1;
+
+        while !self.anchors.insert(anchor.clone()) {
+            n += 1;
+            anchor = format!("{}-{}", base, n);
+        }
+
+        anchor
+    }
+}
+
+impl<W: Write> Backend for Html<W> {
+    fn begin_mailbox(&mut self, source: &str) -> io::Result<()> {
+        self.start()?;
+        self.mailboxes += 1;
+
+        writeln!(
+            self.out,
+            "<h1 class=\"papr-{}\">{}</h1>",
+            Role::Source.name(),
+            escape(source)
+        )
+    }
+
+    fn begin_message(&mut self, index: usize, message: &Message) -> io::Result<()> {
+        self.start()?;
+
+        let anchor = self.anchor(index, message);
+        self.in_message = true;
+
+        writeln!(
+            self.out,
+            "<article class=\"papr-message\" id=\"{0}\"><a class=\"papr-anchor\" href=\"#{0}\">#</a>",
+            anchor
+        )?;
+        write!(self.out, "<pre>")
+    }
+
+    fn line(&mut self, spans: &[Span]) -> io::Result<()> {
+        self.start()?;
+
+        // Lines outside of a message have no `<pre>` keeping their spacing
+        if self.in_message {
+            write_line(&mut self.out, spans)
+        } else {
+            write!(self.out, "<div class=\"papr-line\">")?;
+            write_line(&mut self.out, spans)?;
+            writeln!(self.out, "</div>")
+        }
+    }
+
+    fn end_message(&mut self) -> io::Result<()> {
+        self.in_message = false;
+        writeln!(self.out, "</pre></article>")
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.start()?;
+
+        if self.in_message {
+            self.end_message()?;
+        }
+
+        writeln!(self.out, "</body>")?;
+        writeln!(self.out, "</html>")?;
+        self.out.flush()
+    }
+}
+
+/// Writes `spans` as one line of HTML, each span with a role is wrapped in a `papr-<role>` class.
+/// The syntax role of a span is a second class, whose rules come later in the stylesheet
+pub fn write_line(out: &mut impl Write, spans: &[Span]) -> io::Result<()> {
+    for span in spans {
+        match span.syntax {
+            None if span.role == Role::Text => write!(out, "{}", escape(&span.text))?,
+            None => write!(
+                out,
+                "<span class=\"papr-{}\">{}</span>",
+                span.role.name(),
+                escape(&span.text)
+            )?,
+            Some(syntax) => write!(
+                out,
+                "<span class=\"papr-{} papr-{}\">{}</span>",
+                span.role.name(),
+                syntax.name(),
+                escape(&span.text)
+            )?,
+        }
+    }
+
+    writeln!(out)
+}
+
+/// The CSS rules for every role styled by `theme`
+pub fn stylesheet(theme: &Theme) -> String {
+    let text = theme.style(Role::Text);
+    let (background, color) = match theme.is_dark() {
+        true => ("#1c1c1c", "#e4e4e4"),
+        false => ("#ffffff", "#1c1c1c"),
+    };
+    let background = text.bg.map(css_color).unwrap_or(background.to_string());
+    let color = text.fg.map(css_color).unwrap_or(color.to_string());
+    let mut css = String::new();
+
+    let _ = writeln!(
+        css,
+        ".papr {{ margin: 0; padding: 1em; background: {}; color: {}; }}",
+        background, color,
+    );
+    let _ = writeln!(
+        css,
+        ".papr pre {{ margin: 0 0 1em 0; font-family: monospace; white-space: pre-wrap; background: {}; color: {}; }}",
+        background, color,
+    );
+    let _ = writeln!(
+        css,
+        ".papr-line {{ font-family: monospace; white-space: pre-wrap; }}"
+    );
+    let _ = writeln!(
+        css,
+        ".papr-anchor {{ float: right; color: inherit; opacity: 0.4; text-decoration: none; }}"
+    );
+    let _ = writeln!(css, ".papr-anchor:hover {{ opacity: 1; }}");
+
+    for role in Role::ALL {
+        let style = theme.style(role);
+
+        if role != Role::Text && !style.is_plain() {
+            let _ = writeln!(css, ".papr-{} {{ {} }}", role.name(), declarations(&style));
+        }
+    }
+
+    css
+}
+
+fn declarations(style: &Style) -> String {
+    let mut declarations = Vec::new();
+
+    if let Some(fg) = style.fg {
+        declarations.push(format!("color: {};", css_color(fg)));
+    }
+    if let Some(bg) = style.bg {
+        declarations.push(format!("background: {};", css_color(bg)));
+    }
+    if style.bold {
+        declarations.push("font-weight: bold;".to_string());
+    }
+    if style.dim {
+        declarations.push("opacity: 0.6;".to_string());
+    }
+    if style.italic {
+        declarations.push("font-style: italic;".to_string());
+    }
+    if style.underline {
+        declarations.push("text-decoration: underline;".to_string());
+    }
+
+    declarations.join(" ")
+}
+
+/// The CSS value of `color`, palette colors use the xterm values
+fn css_color(color: Color) -> String {
+    const BASIC: [(u8, u8, u8); 16] = [
+        (0x00, 0x00, 0x00),
+        (0xcd, 0x00, 0x00),
+        (0x00, 0xcd, 0x00),
+        (0xcd, 0xcd, 0x00),
+        (0x00, 0x00, 0xee),
+        (0xcd, 0x00, 0xcd),
+        (0x00, 0xcd, 0xcd),
+        (0xe5, 0xe5, 0xe5),
+        (0x7f, 0x7f, 0x7f),
+        (0xff, 0x00, 0x00),
+        (0x00, 0xff, 0x00),
+        (0xff, 0xff, 0x00),
+        (0x5c, 0x5c, 0xff),
+        (0xff, 0x00, 0xff),
+        (0x00, 0xff, 0xff),
+        (0xff, 0xff, 0xff),
+    ];
+    const LEVELS: [u8; 6] = [0x00, 0x5f, 0x87, 0xaf, 0xd7, 0xff];
+
+    let (r, g, b) = match color {
+        Color::Black => BASIC[0],
+        Color::Red => BASIC[1],
+        Color::Green => BASIC[2],
+        Color::Yellow => BASIC[3],
+        Color::Blue => BASIC[4],
+        Color::Magenta => BASIC[5],
+        Color::Cyan => BASIC[6],
+        Color::White => BASIC[7],
+        Color::Fixed(n @ 0..=15) => BASIC[usize::from(n)],
+        Color::Fixed(n @ 16..=231) => {
+            let n = usize::from(n - 16);
+            (LEVELS[n / 36], LEVELS[n / 6 % 6], LEVELS[n % 6])
+        }
+        Color::Fixed(n) => {
+            let gray = 8 + (n - 232) * 10;
+            (gray, gray, gray)
+        }
+        Color::Rgb(r, g, b) => (r, g, b),
+    };
+
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+/// Escapes the characters with special meaning in HTML
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+/// Turns `text` in something safe to use as an anchor or a file name
+pub fn slug(text: &str) -> String {
+    text.trim()
+        .trim_matches(|c| c == '<' || c == '>')
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-' | '@') {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mailbox::Mailbox, render};
+
+    #[test]
+    fn html_mailbox() {
+        let input = include_str!("../mailbox/samples/multi_patches.mbx");
+        let mailbox = Mailbox::try_from(input).unwrap();
+        let mut html = Html::new(Vec::new(), Theme::light());
+        render::mailbox(
+            "multi_patches.mbx",
+            &mailbox,
+            &render::Options::default(),
+            &mut html,
+        )
+        .unwrap();
+        html.finish().unwrap();
+
+        let output = String::from_utf8(html.out).unwrap();
+        assert!(output.starts_with("<!DOCTYPE html>"));
+        assert!(output.trim_end().ends_with("</html>"));
+        assert!(output.contains(".papr-email { color: #870087; }"));
+        assert!(output.contains("<span class=\"papr-email\">john.doe@email.com</span>"));
+        assert!(output.contains("&lt;<span"));
+
+        // Every message of the sample has the same Message-Id
+        assert!(output.contains("id=\"20220608-john-doe@email.com\""));
+        assert!(output.contains("id=\"20220608-john-doe@email.com-2\""));
+        assert!(output.contains("id=\"20220608-john-doe@email.com-3\""));
+    }
+
+    #[test]
+    fn css_colors() {
+        assert_eq!(css_color(Color::Red), "#cd0000");
+        assert_eq!(css_color(Color::Fixed(208)), "#ff8700");
+        assert_eq!(css_color(Color::Fixed(244)), "#808080");
+        assert_eq!(css_color(Color::Rgb(1, 2, 3)), "#010203");
+    }
+
+    #[test]
+    fn page_colors() {
+        assert!(stylesheet(&Theme::dark()).contains("background: #1c1c1c; color: #e4e4e4;"));
+        assert!(stylesheet(&Theme::light()).contains("background: #ffffff; color: #1c1c1c;"));
+
+        let mut theme = Theme::dark();
+        theme.set(Role::Text, Style::new().fg(Color::Black).bg(Color::White));
+        assert!(stylesheet(&theme).contains(".papr pre { margin: 0 0 1em 0; font-family: monospace; white-space: pre-wrap; background: #e5e5e5; color: #000000; }"));
+    }
+
+    #[test]
+    fn escape_html() {
+        assert_eq!(escape("<a href=\"x\">&'"), "&lt;a href=&quot;x&quot;&gt;&amp;&#39;");
+        assert_eq!(slug("<foo bar@baz.com>"), "foo-bar@baz.com");
+    }
+}
@@ -0,0 +1,599 @@
+//! The spans of the part of a patch after its `---` line, rendered from its [`Patch`] model
+
+use std::ops::Range;
+
+use crate::{
+    diff::{self, words, Binary, Change, Hunk, Kind, Patch, Payload},
+    syntax::{self, Language, State, Token},
+    whitespace::{self, Rule},
+};
+
+use super::{Options, Role, Span};
+
+mod side_by_side;
+
+/// Splits the part of a body after the `---` (the diffstat, the diff and the signature) in lines
+/// styled according to where they are in the patch
+pub fn lines<'a>(text: &'a str, options: &Options) -> Vec<Vec<Span<'a>>> {
+    let patch = Patch::parse(text);
+    let mut lines = patch
+        .preamble
+        .iter()
+        .map(|line| diffstat_spans(line.trim_end_matches('\r')))
+        .collect::<Vec<_>>();
+
+    for file in &patch.files {
+        let language = file
+            .path()
+            .and_then(Language::from_path)
+            .filter(|_| options.syntax);
+
+        // The extended header lines are summarized after the `diff` line
+        let (first, header) = file.header.split_at(file.header.len().min(1));
+        let summaries = file.changes().into_iter().map(change_summary);
+
+        for line in first {
+            lines.push(vec![Span::new(
+                Role::DiffHeader,
+                line.trim_end_matches('\r'),
+            )]);
+        }
+
+        lines.extend(summaries.map(|summary| vec![Span::new(Role::DiffHeader, summary)]));
+
+        for line in header.iter().filter(|line| !diff::is_extended_header(line)) {
+            lines.push(vec![Span::new(
+                Role::DiffHeader,
+                line.trim_end_matches('\r'),
+            )]);
+        }
+
+        if let Some(binary) = &file.binary {
+            if options.binary {
+                lines.extend(
+                    binary
+                        .lines
+                        .iter()
+                        .map(|line| vec![Span::new(Role::Context, line.trim_end_matches('\r'))]),
+                );
+            } else {
+                lines.push(vec![Span::new(Role::DiffHeader, binary_summary(binary))]);
+            }
+        }
+
+        for hunk in &file.hunks {
+            lines.push(vec![Span::new(
+                Role::HunkHeader,
+                hunk.header.trim_end_matches('\r'),
+            )]);
+
+            let unified = hunk_lines(hunk, language, options);
+            let rows = options
+                .side_by_side
+                .and_then(|width| side_by_side::rows(hunk, &unified, width));
+
+            lines.extend(match rows {
+                Some(rows) => rows,
+                None if options.line_numbers => numbered(hunk, unified),
+                None => unified,
+            });
+        }
+    }
+
+    // Only the signature is expected after the diff, anything else is left as is
+    let signature = patch
+        .rest
+        .first()
+        .is_some_and(|line| diff::is_signature(line));
+
+    for line in &patch.rest {
+        let role = if signature {
+            Role::Signature
+        } else {
+            Role::Text
+        };
+        lines.push(vec![Span::new(role, line.trim_end_matches('\r'))]);
+    }
+
+    lines
+}
+
+/// A compact line telling a change of git's extended header, like `renamed a.c → b.c (95%)`
+fn change_summary(change: Change) -> String {
+    let similarity = |similarity: Option<u8>| match similarity {
+        Some(similarity) => format!(" ({}%)", similarity),
+        None => String::new(),
+    };
+
+    match change {
+        Change::Renamed {
+            from,
+            to,
+            similarity: percents,
+        } => format!("renamed {} \u{2192} {}{}", from, to, similarity(percents)),
+        Change::Copied {
+            from,
+            to,
+            similarity: percents,
+        } => format!("copied {} \u{2192} {}{}", from, to, similarity(percents)),
+        Change::Created { mode } => format!("new file, mode {}", mode),
+        Change::Deleted { mode } => format!("deleted file, mode {}", mode),
+        Change::Mode { old, new } => format!("mode changed {} \u{2192} {}", old, new),
+    }
+}
+
+/// The line collapsing a binary patch, like `binary file, 4.2 KiB literal`
+fn binary_summary(binary: &Binary) -> String {
+    match binary.payloads.first() {
+        Some(Payload::Literal(size)) => format!("binary file, {} literal", human_size(*size)),
+        Some(Payload::Delta(size)) => format!("binary file, {} delta", human_size(*size)),
+        None => "binary file".to_string(),
+    }
+}
+
+/// A number of bytes in the largest binary unit it reaches, like `4.2 KiB`
+fn human_size(bytes: usize) -> String {
+    const UNITS: [&str; 3] = ["KiB", "MiB", "GiB"];
+
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+/// The lines of a hunk, with the words changed between paired removed and added lines
+/// emphasized, the whitespace errors of the added lines highlighted and the syntax of the code
+/// highlighted when its `language` is known
+fn hunk_lines<'a>(
+    hunk: &Hunk<'a>,
+    language: Option<Language>,
+    options: &Options,
+) -> Vec<Vec<Span<'a>>> {
+    // The parts of each line styled differently from the rest of the line, those coming last
+    // take precedence. They start after the prefix of the line
+    let mut marks = vec![Vec::new(); hunk.lines.len()];
+
+    if let Some(tokenizer) = &options.word_diff {
+        for (removed, added) in words::pairs(hunk) {
+            let changes = words::changes(
+                hunk.lines[removed].content(),
+                hunk.lines[added].content(),
+                tokenizer,
+            );
+
+            if let Some(changes) = changes {
+                marks[removed].extend(changes.removed.into_iter().map(|r| (r, Role::RemovedWord)));
+                marks[added].extend(changes.added.into_iter().map(|r| (r, Role::AddedWord)));
+            }
+        }
+    }
+
+    let rules = options.whitespace;
+    let mut lines = Vec::with_capacity(hunk.lines.len());
+    // The old and new files can be in different comments or strings at the same context line
+    let mut old = State::default();
+    let mut new = State::default();
+
+    for (index, line) in hunk.lines.iter().enumerate() {
+        let tokens = match (language, line.kind) {
+            (Some(language), Kind::Context) => {
+                syntax::highlight(language, line.content(), &mut old);
+                syntax::highlight(language, line.content(), &mut new)
+            }
+            (Some(language), Kind::Added) => syntax::highlight(language, line.content(), &mut new),
+            (Some(language), Kind::Removed) => {
+                syntax::highlight(language, line.content(), &mut old)
+            }
+            _ => Vec::new(),
+        };
+        let syntax = tokens
+            .into_iter()
+            .map(|(range, token)| (range, syntax_role(token)))
+            .collect::<Vec<_>>();
+
+        let spans = match line.kind {
+            Kind::Added => {
+                let raw = if rules.contains(Rule::Crlf) {
+                    line.raw
+                } else {
+                    line.raw.trim_end_matches('\r')
+                };
+
+                marks[index].extend(
+                    whitespace::line_errors(&raw[line.columns..], rules)
+                        .into_iter()
+                        .map(|error| (error.range, Role::Whitespace)),
+                );
+
+                let mut spans =
+                    marked_spans(raw, line.columns, Role::Added, &marks[index], &syntax);
+
+                // Like git, the `\r` of a CRLF line ending is shown as `^M`
+                if let Some(span) = spans.last_mut().filter(|span| span.text.ends_with('\r')) {
+                    span.text = span.text.replace('\r', "^M").into();
+                }
+
+                spans
+            }
+            Kind::Removed => marked_spans(
+                line.raw.trim_end_matches('\r'),
+                line.columns,
+                Role::Removed,
+                &marks[index],
+                &syntax,
+            ),
+            Kind::NoNewline
+                if rules.contains(Rule::MissingNewline)
+                    && index > 0
+                    && hunk.lines[index - 1].kind == Kind::Added =>
+            {
+                vec![Span::new(Role::Whitespace, line.raw.trim_end_matches('\r'))]
+            }
+            Kind::Context => marked_spans(
+                line.raw.trim_end_matches('\r'),
+                line.columns,
+                Role::Context,
+                &[],
+                &syntax,
+            ),
+            Kind::NoNewline => {
+                vec![Span::new(Role::Context, line.raw.trim_end_matches('\r'))]
+            }
+        };
+
+        lines.push(spans);
+    }
+
+    lines
+}
+
+/// Prefixes the `lines` of `hunk` with their numbers in the old and the new file, a side is left
+/// blank for the lines it doesn't have. Lines are left as they are when the header of the hunk
+/// can't be parsed or its numbers overflow, and for the combined diffs of merges
+fn numbered<'a>(hunk: &Hunk, mut lines: Vec<Vec<Span<'a>>>) -> Vec<Vec<Span<'a>>> {
+    let (Some((mut old, old_count)), Some((mut new, new_count))) = (hunk.old, hunk.new) else {
+        return lines;
+    };
+
+    if hunk.columns > 1 {
+        return lines;
+    }
+
+    let Some(end) = old
+        .checked_add(old_count)
+        .zip(new.checked_add(new_count))
+        .map(|(old, new)| old.max(new))
+    else {
+        return lines;
+    };
+    let digits = end.to_string().len();
+    let number = |number: Option<usize>| match number {
+        Some(number) => format!("{:>width$}", number, width = digits),
+        None => " ".repeat(digits),
+    };
+
+    for (line, spans) in hunk.lines.iter().zip(&mut lines) {
+        let (left, right) = match line.kind {
+            Kind::Context => (Some(old), Some(new)),
+            Kind::Removed => (Some(old), None),
+            Kind::Added => (None, Some(new)),
+            Kind::NoNewline => (None, None),
+        };
+
+        old = old.saturating_add(usize::from(left.is_some()));
+        new = new.saturating_add(usize::from(right.is_some()));
+        spans.insert(
+            0,
+            Span::new(
+                Role::LineNumber,
+                format!("{} {}\u{2502}", number(left), number(right)),
+            ),
+        );
+    }
+
+    lines
+}
+
+/// Splits a line of a hunk in spans of `role`, except for the `marks` given after its prefix of
+/// `columns` characters. Where marks overlap the last one wins. The `syntax` roles, also given
+/// after the prefix, are layered over the others. Each column of the prefix is styled by its own
+/// `+` or `-`, for the parents of combined diffs
+fn marked_spans<'a>(
+    line: &'a str,
+    columns: usize,
+    role: Role,
+    marks: &[(Range<usize>, Role)],
+    syntax: &[(Range<usize>, Role)],
+) -> Vec<Span<'a>> {
+    let mut roles = vec![(role, None); line.len()];
+    // The ranges are found in the content of the line, which may keep `\r`s trimmed from `line`
+    let after_prefix = |range: &Range<usize>| {
+        let end = (range.end + columns).min(line.len());
+        (range.start + columns).min(end)..end
+    };
+
+    for (at, c) in line.bytes().take(columns).enumerate() {
+        roles[at].0 = match c {
+            b'+' => Role::Added,
+            b'-' => Role::Removed,
+            _ => Role::Context,
+        };
+    }
+
+    for (range, mark) in marks {
+        roles[after_prefix(range)]
+            .iter_mut()
+            .for_each(|(role, _)| *role = *mark);
+    }
+
+    for (range, token) in syntax {
+        roles[after_prefix(range)]
+            .iter_mut()
+            .for_each(|(_, syntax)| *syntax = Some(*token));
+    }
+
+    let mut spans = Vec::new();
+    let mut start = 0;
+
+    for at in 1..=line.len() {
+        if at == line.len() || roles[at] != roles[start] {
+            let (role, syntax) = roles[start];
+            spans.push(Span::new(role, &line[start..at]).with_syntax(syntax));
+            start = at;
+        }
+    }
+
+    if spans.is_empty() {
+        spans.push(Span::new(role, line));
+    }
+
+    spans
+}
+
+/// The role rendering a syntax token
+fn syntax_role(token: Token) -> Role {
+    match token {
+        Token::Keyword => Role::Keyword,
+        Token::Type => Role::Type,
+        Token::String => Role::String,
+        Token::Number => Role::Number,
+        Token::Comment => Role::Comment,
+        Token::Meta => Role::Meta,
+    }
+}
+
+/// Styles the `+` and `-` bars of a diffstat line like ` file.c | 3 ++-`
+fn diffstat_spans(line: &str) -> Vec<Span<'_>> {
+    let Some((file, stat)) = line.rsplit_once('|') else {
+        return vec![Span::text(line)];
+    };
+    let bars = stat.trim_start_matches(|c: char| c != '+' && c != '-');
+    let added = bars.trim_end_matches('-');
+
+    vec![
+        Span::text(&line[..file.len() + 1 + stat.len() - bars.len()]),
+        Span::new(Role::Added, added),
+        Span::new(Role::Removed, &bars[added.len()..]),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::whitespace::Rules;
+
+    #[test]
+    fn render_whitespace_errors() {
+        let text = "diff --git a/foo.c b/foo.c\n@@ -1 +1,2 @@\n+int foo; \r\n+int bar;\n\\ No newline at end of file\n";
+        let lines = |whitespace| {
+            let options = Options {
+                whitespace,
+                word_diff: None,
+                syntax: false,
+                side_by_side: None,
+                line_numbers: false,
+                diffstat: None,
+                binary: false,
+            };
+            lines(text, &options).split_off(2)
+        };
+
+        assert_eq!(
+            lines(Rules::default()),
+            [
+                vec![
+                    Span::new(Role::Added, "+int foo;"),
+                    Span::new(Role::Whitespace, " ^M"),
+                ],
+                vec![Span::new(Role::Added, "+int bar;")],
+                vec![Span::new(Role::Whitespace, "\\ No newline at end of file")],
+            ]
+        );
+        assert_eq!(
+            lines(Rules::NONE),
+            [
+                vec![Span::new(Role::Added, "+int foo; ")],
+                vec![Span::new(Role::Added, "+int bar;")],
+                vec![Span::new(Role::Context, "\\ No newline at end of file")],
+            ]
+        );
+    }
+
+    #[test]
+    fn render_changed_words() {
+        let text = "diff --git a/foo.c b/foo.c\n@@ -1,2 +1,2 @@\n-\tfoo(dev, 1);\n-\treturn 0;\n+\tfoo(dev, 2); \n+\tkfree(buf);\n";
+        let options = Options {
+            syntax: false,
+            ..Options::default()
+        };
+        let lines = lines(text, &options);
+
+        assert_eq!(
+            lines[2..],
+            [
+                vec![
+                    Span::new(Role::Removed, "-\tfoo(dev, "),
+                    Span::new(Role::RemovedWord, "1"),
+                    Span::new(Role::Removed, ");"),
+                ],
+                vec![Span::new(Role::Removed, "-\treturn 0;")],
+                vec![
+                    Span::new(Role::Added, "+\tfoo(dev, "),
+                    Span::new(Role::AddedWord, "2"),
+                    Span::new(Role::Added, ");"),
+                    Span::new(Role::Whitespace, " "),
+                ],
+                vec![Span::new(Role::Added, "+\tkfree(buf);")],
+            ]
+        );
+    }
+
+    #[test]
+    fn render_changed_words_before_carriage_returns() {
+        let text = "diff --git a/foo.c b/foo.c\n@@ -1 +1 @@\n-xxxx\r\r\r\r\n+xxxx\r\r\n";
+        let options = Options {
+            syntax: false,
+            word_diff: Some(crate::diff::words::Tokenizer::Char),
+            ..Options::default()
+        };
+        let texts = lines(text, &options)[2..]
+            .iter()
+            .map(|line| line.iter().map(|span| span.text.as_ref()).collect())
+            .collect::<Vec<String>>();
+
+        assert_eq!(texts, ["-xxxx", "+xxxx\r^M"]);
+    }
+
+    #[test]
+    fn render_syntax() {
+        let text =
+            "diff --git a/foo.c b/foo.c\n@@ -1,2 +1,2 @@\n-/* old\n+int x;\n  * y */ return;\n";
+        let lines = lines(text, &Options::default());
+
+        assert_eq!(
+            lines[2..],
+            [
+                vec![
+                    Span::new(Role::Removed, "-"),
+                    Span::new(Role::Removed, "/* old").with_syntax(Some(Role::Comment)),
+                ],
+                vec![
+                    Span::new(Role::Added, "+"),
+                    Span::new(Role::Added, "int").with_syntax(Some(Role::Type)),
+                    Span::new(Role::Added, " x;"),
+                ],
+                // Still in the comment in the old file but not in the new one
+                vec![
+                    Span::new(Role::Context, "  * y */ "),
+                    Span::new(Role::Context, "return").with_syntax(Some(Role::Keyword)),
+                    Span::new(Role::Context, ";"),
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn render_line_numbers() {
+        let text =
+            "diff --git a/f b/f\n@@ -9,3 +9,3 @@\n a\n-b\n+c\n d\n\\ No newline at end of file\n";
+        let options = Options {
+            syntax: false,
+            word_diff: None,
+            line_numbers: true,
+            ..Options::default()
+        };
+        let lines = lines(text, &options);
+
+        assert_eq!(
+            lines[2..]
+                .iter()
+                .map(|line| line.iter().map(|span| span.text.as_ref()).collect())
+                .collect::<Vec<String>>(),
+            [
+                " 9  9\u{2502} a",
+                "10   \u{2502}-b",
+                "   10\u{2502}+c",
+                "11 11\u{2502} d",
+                "     \u{2502}\\ No newline at end of file",
+            ]
+        );
+
+        let text = "diff --git a/f b/f\n@@ -18446744073709551615,3 +1,3 @@\n a\n-b\n+c\n d\n";
+        assert_eq!(super::lines(text, &options)[2][0].text, " a");
+    }
+
+    #[test]
+    fn render_file_summaries() {
+        let text = "diff --git a/a.c b/b.c\nsimilarity index 95%\nrename from a.c\nrename to b.c\nindex 1234567..89abcde 100644\ndiff --git a/logo.png b/logo.png\nnew file mode 100644\nGIT binary patch\nliteral 4300\nzcmV;fV|5gdJ+\n\nliteral 0\nHcmV?d00001\n\n";
+        let texts = |options: &Options| {
+            lines(text, options)
+                .iter()
+                .map(|line| line.iter().map(|span| span.text.as_ref()).collect())
+                .collect::<Vec<String>>()
+        };
+
+        assert_eq!(
+            texts(&Options::default()),
+            [
+                "diff --git a/a.c b/b.c",
+                "renamed a.c \u{2192} b.c (95%)",
+                "index 1234567..89abcde 100644",
+                "diff --git a/logo.png b/logo.png",
+                "new file, mode 100644",
+                "binary file, 4.2 KiB literal",
+            ]
+        );
+        assert_eq!(
+            texts(&Options {
+                binary: true,
+                ..Options::default()
+            })[5..],
+            [
+                "GIT binary patch",
+                "literal 4300",
+                "zcmV;fV|5gdJ+",
+                "",
+                "literal 0",
+                "HcmV?d00001",
+                "",
+            ]
+        );
+        assert_eq!(human_size(1023), "1023 B");
+        assert_eq!(human_size(3 << 20), "3.0 MiB");
+    }
+
+    #[test]
+    fn render_combined_diff() {
+        let text = "diff --cc f\n@@@ -1,2 -1,1 +1,2 @@@\n- b\n -c\n++d\n +f\n";
+        let options = Options {
+            word_diff: None,
+            ..Options::default()
+        };
+
+        assert_eq!(
+            lines(text, &options)[2..],
+            [
+                vec![
+                    Span::new(Role::Removed, "-"),
+                    Span::new(Role::Context, " "),
+                    Span::new(Role::Removed, "b"),
+                ],
+                vec![
+                    Span::new(Role::Context, " "),
+                    Span::new(Role::Removed, "-c"),
+                ],
+                vec![Span::new(Role::Added, "++d")],
+                vec![Span::new(Role::Context, " "), Span::new(Role::Added, "+f"),],
+            ]
+        );
+    }
+}
@@ -0,0 +1,299 @@
+//! Hunks rendered in two columns, the old file on the left and the new one on the right, with the
+//! line numbers of both files
+
+use std::borrow::Cow;
+
+use crate::diff::{Hunk, Kind};
+
+use super::super::{Role, Span};
+
+/// Below this many characters of code per column, hunks are left unified
+pub const MIN_COLUMN_WIDTH: usize = 30;
+
+/// Tabs are expanded to align the columns
+const TAB_WIDTH: usize = 8;
+
+/// A line of one of the columns: its number, if any, and its spans without the prefix
+type Cell<'a> = (Option<usize>, Vec<Span<'a>>);
+
+/// A part of a wrapped line and its width
+type Piece<'a> = (Vec<Span<'a>>, usize);
+
+/// Lays out the `lines` of `hunk`, rendered unified, in two columns fitting in `width`. Removed
+/// lines face the added lines following them and long lines are wrapped. `None` when the header
+/// of the hunk can't be parsed, for the combined diffs of merges which have more than two sides,
+/// when `width` is too narrow for two columns, or when its line numbers overflow
+pub fn rows<'a>(hunk: &Hunk, lines: &[Vec<Span<'a>>], width: usize) -> Option<Vec<Vec<Span<'a>>>> {
+    let ((mut old, old_count), (mut new, new_count)) = (hunk.old?, hunk.new?);
+
+    if hunk.columns > 1 {
+        return None;
+    }
+
+    let digits = old
+        .checked_add(old_count)?
+        .max(new.checked_add(new_count)?)
+        .to_string()
+        .len();
+    let column = (width.saturating_sub(1) / 2).checked_sub(digits + 1)?;
+
+    if column < MIN_COLUMN_WIDTH {
+        return None;
+    }
+
+    let mut pairs: Vec<(Option<Cell>, Option<Cell>)> = Vec::new();
+    let mut index = 0;
+
+    while let Some(line) = hunk.lines.get(index) {
+        let spans = without_prefix(&lines[index], line.kind);
+
+        if line.kind != Kind::Removed && line.kind != Kind::Added {
+            // A `\` line following a context line is about both files
+            let (old, new) = match line.kind {
+                Kind::Context => (Some(next(&mut old)?), Some(next(&mut new)?)),
+                _ => (None, None),
+            };
+
+            pairs.push((Some((old, spans.clone())), Some((new, spans))));
+            index += 1;
+            continue;
+        }
+
+        // A run of removed lines and the run of added lines following it
+        let mut left = Vec::new();
+        let mut right: Vec<Cell> = Vec::new();
+
+        while let Some(line) = hunk.lines.get(index) {
+            let previous = index.checked_sub(1).map(|index| hunk.lines[index].kind);
+            let (side, number) = match line.kind {
+                Kind::Removed if right.is_empty() => (&mut left, Some(&mut old)),
+                Kind::Added => (&mut right, Some(&mut new)),
+                Kind::NoNewline if previous == Some(Kind::Removed) && right.is_empty() => {
+                    (&mut left, None)
+                }
+                Kind::NoNewline if previous == Some(Kind::Added) => (&mut right, None),
+                _ => break,
+            };
+            let number = match number {
+                Some(number) => Some(next(number)?),
+                None => None,
+            };
+
+            side.push((number, without_prefix(&lines[index], line.kind)));
+            index += 1;
+        }
+
+        let height = left.len().max(right.len());
+        let mut left = left.into_iter();
+        let mut right = right.into_iter();
+
+        pairs.extend((0..height).map(|_| (left.next(), right.next())));
+    }
+
+    let mut rows = Vec::new();
+
+    for (left, right) in pairs {
+        let left = left.map(|(number, spans)| (number, wrap(spans, column)));
+        let right = right.map(|(number, spans)| (number, wrap(spans, column)));
+        let height = [&left, &right]
+            .iter()
+            .map(|cell| cell.as_ref().map_or(1, |(_, pieces)| pieces.len()))
+            .max()
+            .unwrap_or(1);
+
+        for row in 0..height {
+            let mut spans = Vec::new();
+
+            push_cell(&mut spans, &left, row, digits, column);
+            spans.push(Span::new(Role::LineNumber, "\u{2502}"));
+            push_cell(&mut spans, &right, row, digits, column);
+
+            // Nothing is padded after the right column
+            while spans
+                .last()
+                .is_some_and(|span| span.role == Role::Text && span.text.chars().all(|c| c == ' '))
+            {
+                spans.pop();
+            }
+
+            rows.push(spans);
+        }
+    }
+
+    Some(rows)
+}
+
+/// The number of the current line, moving `number` to the next one. `None` when it overflows
+fn next(number: &mut usize) -> Option<usize> {
+    let current = *number;
+    *number = current.checked_add(1)?;
+    Some(current)
+}
+
+/// The spans of a line without its `+`, `-` or ` ` prefix
+fn without_prefix<'a>(spans: &[Span<'a>], kind: Kind) -> Vec<Span<'a>> {
+    let mut spans = spans.to_vec();
+
+    if kind == Kind::NoNewline {
+        return spans;
+    }
+
+    if let Some(first) = spans.first_mut() {
+        first.text = match &first.text {
+            Cow::Borrowed(text) => Cow::Borrowed(text.get(1..).unwrap_or("")),
+            Cow::Owned(text) => Cow::Owned(text.get(1..).unwrap_or("").to_string()),
+        };
+    }
+
+    spans.retain(|span| !span.text.is_empty());
+    spans
+}
+
+/// Splits `spans` in pieces at most `width` characters wide, with their width. Tabs are expanded
+fn wrap<'a>(spans: Vec<Span<'a>>, width: usize) -> Vec<Piece<'a>> {
+    let mut pieces = vec![(Vec::new(), 0)];
+    let mut column = 0;
+
+    for span in spans {
+        let mut text = String::with_capacity(span.text.len());
+
+        for c in span.text.chars() {
+            if c == '\t' {
+                let spaces = TAB_WIDTH - column % TAB_WIDTH;
+                text.extend(std::iter::repeat_n(' ', spaces));
+                column += spaces;
+            } else {
+                text.push(c);
+                column += 1;
+            }
+        }
+
+        let mut rest = text.as_str();
+
+        while !rest.is_empty() {
+            if pieces.last().is_some_and(|(_, used)| *used == width) {
+                pieces.push((Vec::new(), 0));
+            }
+
+            let Some((piece, used)) = pieces.last_mut() else {
+                break;
+            };
+            let end = rest
+                .char_indices()
+                .nth(width - *used)
+                .map_or(rest.len(), |(end, _)| end);
+
+            piece.push(Span::new(span.role, rest[..end].to_string()).with_syntax(span.syntax));
+            *used += rest[..end].chars().count();
+            rest = &rest[end..];
+        }
+    }
+
+    pieces
+}
+
+/// Adds the `row`-th piece of a cell and its line number, padded to the width of the column
+fn push_cell<'a>(
+    spans: &mut Vec<Span<'a>>,
+    cell: &Option<(Option<usize>, Vec<Piece<'a>>)>,
+    row: usize,
+    digits: usize,
+    column: usize,
+) {
+    let (number, piece) = match cell {
+        Some((number, pieces)) => (number.filter(|_| row == 0), pieces.get(row)),
+        None => (None, None),
+    };
+
+    match number {
+        Some(number) => spans.push(Span::new(
+            Role::LineNumber,
+            format!("{:>width$} ", number, width = digits),
+        )),
+        None => spans.push(Span::text(" ".repeat(digits + 1))),
+    }
+
+    let used = match piece {
+        Some((piece, used)) => {
+            spans.extend(piece.iter().cloned());
+            *used
+        }
+        None => 0,
+    };
+
+    spans.push(Span::text(" ".repeat(column - used)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{diff::Patch, render::Options};
+
+    /// The text of each row
+    fn texts(text: &str, width: usize) -> Option<Vec<String>> {
+        let options = Options {
+            syntax: false,
+            ..Options::default()
+        };
+        let hunk = &Patch::parse(text).files[0].hunks[0];
+        let lines = super::super::hunk_lines(hunk, None, &options);
+
+        rows(hunk, &lines, width).map(|rows| {
+            rows.iter()
+                .map(|row| row.iter().map(|span| span.text.as_ref()).collect())
+                .collect()
+        })
+    }
+
+    #[test]
+    fn render_side_by_side() {
+        let text = "diff --git a/f b/f\n@@ -8,5 +8,3 @@\n a\n-b\n-c\n+B\n d\n-e\n";
+        let rows = texts(text, 79).unwrap();
+        let column = |text: &str| format!("{:<36}", text);
+
+        assert_eq!(
+            rows,
+            [
+                format!(" 8 {}\u{2502} 8 a", column("a")),
+                format!(" 9 {}\u{2502} 9 B", column("b")),
+                format!("10 {}\u{2502}", column("c")),
+                format!("11 {}\u{2502}10 d", column("d")),
+                format!("12 {}\u{2502}", column("e")),
+            ]
+        );
+    }
+
+    #[test]
+    fn wrap_long_lines() {
+        let text = format!(
+            "diff --git a/f b/f\n@@ -1 +1 @@\n-\t{}\n+x\n",
+            "y".repeat(40)
+        );
+        let rows = texts(&text, 79).unwrap();
+
+        assert_eq!(rows.len(), 2);
+        assert!(rows[0].starts_with(&format!("1         {}\u{2502}1 x", "y".repeat(29))));
+        assert!(rows[1].starts_with(&format!("  {} ", "y".repeat(11))));
+    }
+
+    #[test]
+    fn too_narrow() {
+        assert_eq!(texts("diff --git a/f b/f\n@@ -1 +1 @@\n-a\n+b\n", 60), None);
+        assert_eq!(
+            texts("diff --git a/f b/f\n@@ bogus @@\n-a\n+b\n", 200),
+            None
+        );
+        assert_eq!(
+            texts(
+                "diff --git a/f b/f\n@@ -18446744073709551615,3 +1,3 @@\n a\n-b\n+c\n d\n",
+                200
+            ),
+            None
+        );
+        assert!(texts(
+            "diff --git a/f b/f\n@@ -18446744073709551614 +1,2 @@\n a\n+b\n",
+            200
+        )
+        .is_some());
+    }
+}
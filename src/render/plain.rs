@@ -0,0 +1,54 @@
+use std::io::{self, Write};
+
+use super::{Backend, Span};
+
+/// Renders spans as text without any styling
+pub struct Plain<W: Write> {
+    out: W,
+}
+
+impl<W: Write> Plain<W> {
+    pub fn new(out: W) -> Self {
+        Plain { out }
+    }
+}
+
+impl<W: Write> Backend for Plain<W> {
+    fn line(&mut self, spans: &[Span]) -> io::Result<()> {
+        for span in spans {
+            write!(self.out, "{}", span.text)?;
+        }
+
+        writeln!(self.out)
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mailbox::Mailbox, render};
+
+    #[test]
+    fn plain_mailbox() {
+        let input = include_str!("../mailbox/samples/multi_patches.mbx");
+        let mailbox = Mailbox::try_from(input).unwrap();
+        let mut plain = Plain::new(Vec::new());
+        render::mailbox(
+            "multi_patches.mbx",
+            &mailbox,
+            &render::Options::default(),
+            &mut plain,
+        )
+        .unwrap();
+
+        let output = String::from_utf8(plain.out).unwrap();
+        assert!(!output.contains('\x1b'));
+        assert!(output.starts_with("multi_patches.mbx:\n"));
+        assert!(output.contains("Subject: [PATCH v1 1/10] patch-tree: foo message\n"));
+        assert!(output.contains("From: John Doe <john.doe@email.com>\n"));
+    }
+}
@@ -0,0 +1,149 @@
+//! The lines of a diffstat, with its `+` and `-` bars scaled to the width of the output
+
+use crate::diff::stat::Stat;
+
+use super::{Role, Span};
+
+/// Paths are shortened rather than leaving less than this many columns to the bars
+const MIN_BARS_WIDTH: usize = 10;
+
+/// Renders `stat` like `git diff --stat` in `width` columns: a line per file then the totals
+pub fn lines(stat: &Stat, width: usize) -> Vec<Vec<Span<'static>>> {
+    let most = stat
+        .files
+        .iter()
+        .map(|file| file.added + file.removed)
+        .max()
+        .unwrap_or(0);
+    let count_width = most.to_string().len();
+    let longest = stat
+        .files
+        .iter()
+        .map(|file| file.path.chars().count())
+        .max()
+        .unwrap_or(0);
+    // A line is ` path | count bars`
+    let path_width = longest.min(width.saturating_sub(count_width + 5 + MIN_BARS_WIDTH));
+    let bars_width = width.saturating_sub(path_width + count_width + 5).max(1);
+    let mut lines = Vec::with_capacity(stat.files.len() + 1);
+
+    for file in &stat.files {
+        let (added, removed) = bars(file.added, file.removed, most, bars_width);
+
+        lines.push(vec![
+            Span::text(format!(
+                " {:<path_width$} | {:>count_width$} ",
+                shorten(&file.path, path_width),
+                file.added + file.removed,
+            )),
+            Span::new(Role::Added, "+".repeat(added)),
+            Span::new(Role::Removed, "-".repeat(removed)),
+        ]);
+    }
+
+    lines.push(vec![Span::text(summary(stat))]);
+    lines
+}
+
+/// The lengths of the `+` and `-` bars of a file, scaled down like git when the largest change
+/// doesn't fit `width`. Changes are at least one character long
+fn bars(added: usize, removed: usize, most: usize, width: usize) -> (usize, usize) {
+    if most <= width {
+        return (added, removed);
+    }
+
+    let scale = |count: usize| match count {
+        0 => 0,
+        count => 1 + count * (width - 1) / most,
+    };
+    let total = scale(added + removed);
+    let added = scale(added).min(total);
+
+    match total - added {
+        0 if removed > 0 && added > 1 => (added - 1, 1),
+        removed => (added, removed),
+    }
+}
+
+/// Keeps the end of a path longer than `width`, like git's `.../foo.c`
+fn shorten(path: &str, width: usize) -> String {
+    let length = path.chars().count();
+
+    if length <= width {
+        return path.to_string();
+    }
+
+    let tail = path
+        .chars()
+        .skip(length - width.saturating_sub(3))
+        .collect::<String>();
+
+    format!("...{}", tail)
+}
+
+/// The ` N files changed, X insertions(+), Y deletions(-)` line closing a diffstat
+fn summary(stat: &Stat) -> String {
+    let plural = |count: usize, singular: &str, plural: &str| {
+        format!("{} {}", count, if count == 1 { singular } else { plural })
+    };
+    let mut summary = format!(" {} changed", plural(stat.files.len(), "file", "files"));
+
+    if stat.added() > 0 {
+        summary += &format!(", {}(+)", plural(stat.added(), "insertion", "insertions"));
+    }
+
+    if stat.removed() > 0 {
+        summary += &format!(", {}(-)", plural(stat.removed(), "deletion", "deletions"));
+    }
+
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::stat::FileStat;
+
+    fn texts(stat: &Stat, width: usize) -> Vec<String> {
+        lines(stat, width)
+            .iter()
+            .map(|line| line.iter().map(|span| span.text.as_ref()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn render_stat() {
+        let stat = Stat {
+            files: vec![
+                FileStat {
+                    path: "foo.c".to_string(),
+                    added: 2,
+                    removed: 1,
+                },
+                FileStat {
+                    path: "drivers/net/bar.c".to_string(),
+                    added: 1,
+                    removed: 0,
+                },
+            ],
+        };
+
+        assert_eq!(
+            texts(&stat, 80),
+            [
+                " foo.c             | 3 ++-",
+                " drivers/net/bar.c | 1 +",
+                " 2 files changed, 3 insertions(+), 1 deletion(-)",
+            ]
+        );
+        assert_eq!(texts(&stat, 26)[1], " ...t/bar.c | 1 +");
+    }
+
+    #[test]
+    fn scale_bars() {
+        assert_eq!(bars(3, 1, 4, 60), (3, 1));
+        assert_eq!(bars(100, 100, 200, 21), (11, 10));
+        assert_eq!(bars(199, 1, 200, 10), (9, 1));
+        assert_eq!(bars(0, 1, 200, 10), (0, 1));
+    }
+}
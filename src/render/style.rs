@@ -0,0 +1,202 @@
+use std::str::FromStr;
+
+use color_eyre::eyre::{bail, eyre};
+
+/// A terminal color
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    /// One of the 256 colors of the extended palette
+    Fixed(u8),
+    /// A truecolor value
+    Rgb(u8, u8, u8),
+}
+
+/// How a piece of text looks like. The default style leaves the text untouched
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Style {
+    pub fg: Option<Color>,
+    pub bg: Option<Color>,
+    pub bold: bool,
+    pub dim: bool,
+    pub italic: bool,
+    pub underline: bool,
+}
+
+impl Style {
+    pub const fn new() -> Self {
+        Style {
+            fg: None,
+            bg: None,
+            bold: false,
+            dim: false,
+            italic: false,
+            underline: false,
+        }
+    }
+
+    pub const fn fg(self, color: Color) -> Self {
+        Style {
+            fg: Some(color),
+            ..self
+        }
+    }
+
+    pub const fn bg(self, color: Color) -> Self {
+        Style {
+            bg: Some(color),
+            ..self
+        }
+    }
+
+    pub const fn bold(self) -> Self {
+        Style { bold: true, ..self }
+    }
+
+    pub const fn dim(self) -> Self {
+        Style { dim: true, ..self }
+    }
+
+    pub const fn italic(self) -> Self {
+        Style {
+            italic: true,
+            ..self
+        }
+    }
+
+    pub const fn underline(self) -> Self {
+        Style {
+            underline: true,
+            ..self
+        }
+    }
+
+    /// This style with `top` layered over it: the colors of `top` replace these ones, and the
+    /// attributes of both apply
+    pub fn layer(self, top: Style) -> Self {
+        Style {
+            fg: top.fg.or(self.fg),
+            bg: top.bg.or(self.bg),
+            bold: self.bold || top.bold,
+            dim: self.dim || top.dim,
+            italic: self.italic || top.italic,
+            underline: self.underline || top.underline,
+        }
+    }
+
+    /// Whether this style changes anything about the text
+    pub fn is_plain(&self) -> bool {
+        *self == Style::default()
+    }
+}
+
+impl FromStr for Color {
+    type Err = color_eyre::Report;
+
+    /// Parses a color name (`red`), a 256 palette index (`208`) or a truecolor value (`#ff8700`)
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let color = match value {
+            "black" => Color::Black,
+            "red" => Color::Red,
+            "green" => Color::Green,
+            "yellow" => Color::Yellow,
+            "blue" => Color::Blue,
+            "magenta" => Color::Magenta,
+            "cyan" => Color::Cyan,
+            "white" => Color::White,
+            _ => {
+                if let Some(hex) = value.strip_prefix('#') {
+                    let channel = |i: usize| {
+                        hex.get(i..i + 2)
+                            .and_then(|c| u8::from_str_radix(c, 16).ok())
+                            .ok_or(eyre!("Invalid truecolor `{}`, expected `#rrggbb`", value))
+                    };
+
+                    if hex.len() != 6 {
+                        bail!("Invalid truecolor `{}`, expected `#rrggbb`", value);
+                    }
+
+                    Color::Rgb(channel(0)?, channel(2)?, channel(4)?)
+                } else {
+                    Color::Fixed(value.parse().map_err(|_| eyre!("Invalid color `{}`", value))?)
+                }
+            }
+        };
+
+        Ok(color)
+    }
+}
+
+impl FromStr for Style {
+    type Err = color_eyre::Report;
+
+    /// Parses a style written like git colors: a list of attributes (`bold`, `dim`, `italic`,
+    /// `ul`) and up to two colors, the first is the foreground and the second the background.
+    /// `normal` can be used as a placeholder color, so `normal blue` only sets the background
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut style = Style::new();
+        let mut colors = 0;
+
+        for word in value.split_whitespace() {
+            match word {
+                "bold" => style.bold = true,
+                "dim" => style.dim = true,
+                "italic" => style.italic = true,
+                "ul" | "underline" => style.underline = true,
+                color => {
+                    let color = match color {
+                        "normal" => None,
+                        _ => Some(color.parse()?),
+                    };
+
+                    match colors {
+                        0 => style.fg = color,
+                        1 => style.bg = color,
+                        _ => bail!("Invalid style `{}`, at most two colors are allowed", value),
+                    }
+
+                    colors += 1;
+                }
+            }
+        }
+
+        Ok(style)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_style() {
+        assert_eq!("".parse::<Style>().unwrap(), Style::new());
+        assert_eq!(
+            "bold red".parse::<Style>().unwrap(),
+            Style::new().bold().fg(Color::Red)
+        );
+        assert_eq!(
+            "208 #1a2B3c ul".parse::<Style>().unwrap(),
+            Style::new()
+                .fg(Color::Fixed(208))
+                .bg(Color::Rgb(0x1a, 0x2b, 0x3c))
+                .underline()
+        );
+        assert_eq!(
+            "normal blue".parse::<Style>().unwrap(),
+            Style::new().bg(Color::Blue)
+        );
+
+        assert!("purple".parse::<Style>().is_err());
+        assert!("256".parse::<Style>().is_err());
+        assert!("#12345".parse::<Style>().is_err());
+        assert!("red green blue".parse::<Style>().is_err());
+    }
+}
@@ -0,0 +1,212 @@
+use std::collections::HashMap;
+
+use super::{
+    style::{Color, Style},
+    Role,
+};
+
+/// Maps roles to the style used to render them, roles without a style are rendered as is
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Theme {
+    styles: HashMap<Role, Style>,
+    /// Whether the theme is meant for a dark background, which HTML pages get when the text role
+    /// has no colors
+    dark: bool,
+}
+
+impl Theme {
+    /// The names of the themes shipped with papr
+    pub const BUILTIN: [&'static str; 3] = ["dark", "light", "monochrome"];
+
+    pub fn builtin(name: &str) -> Option<Theme> {
+        match name {
+            "dark" => Some(Theme::dark()),
+            "light" => Some(Theme::light()),
+            "monochrome" => Some(Theme::monochrome()),
+            _ => None,
+        }
+    }
+
+    /// Basic ANSI colors meant for terminals with a dark background
+    pub fn dark() -> Theme {
+        Theme::from([
+            (Role::Source, Style::new().bold()),
+            (Role::HeaderKey, Style::new().fg(Color::Cyan)),
+            (Role::FromKey, Style::new().bold().fg(Color::Blue)),
+            (Role::DateKey, Style::new().bold().fg(Color::Green)),
+            (Role::AuthorKey, Style::new().bold().fg(Color::Red)),
+            (Role::SubjectKey, Style::new().bold().fg(Color::Yellow)),
+            (Role::Email, Style::new().fg(Color::Magenta)),
+            (Role::Date, Style::new().fg(Color::Green)),
+            (Role::PatchPrefix, Style::new().fg(Color::Yellow)),
+            (Role::PatchVersion, Style::new().fg(Color::Yellow)),
+            (Role::PatchIndex, Style::new().fg(Color::Red)),
+            (Role::Tags, Style::new().fg(Color::Green)),
+            (Role::Separator, Style::new().dim()),
+            (Role::Trailer, Style::new().fg(Color::Cyan)),
+            (Role::SignOff, Style::new().bold().fg(Color::Green)),
+            (Role::Review, Style::new().bold().fg(Color::Blue)),
+            (Role::DiffHeader, Style::new().bold()),
+            (Role::HunkHeader, Style::new().fg(Color::Cyan)),
+            (Role::LineNumber, Style::new().dim()),
+            (Role::Added, Style::new().fg(Color::Green)),
+            (Role::Removed, Style::new().fg(Color::Red)),
+            (
+                Role::AddedWord,
+                Style::new().bold().fg(Color::Black).bg(Color::Green),
+            ),
+            (
+                Role::RemovedWord,
+                Style::new().bold().fg(Color::Black).bg(Color::Red),
+            ),
+            (Role::Whitespace, Style::new().bg(Color::Red)),
+            (Role::Signature, Style::new().dim()),
+            (Role::Keyword, Style::new().fg(Color::Yellow)),
+            (Role::Type, Style::new().fg(Color::Cyan)),
+            (Role::String, Style::new().fg(Color::Magenta)),
+            (Role::Number, Style::new().fg(Color::Magenta)),
+            (Role::Comment, Style::new().dim().italic()),
+            (Role::Meta, Style::new().fg(Color::Blue)),
+        ])
+        .on_dark_background()
+    }
+
+    /// Darker shades of the 256 color palette that stay readable on a light background
+    pub fn light() -> Theme {
+        Theme::from([
+            (Role::Source, Style::new().bold()),
+            (Role::HeaderKey, Style::new().fg(Color::Fixed(30))),
+            (Role::FromKey, Style::new().bold().fg(Color::Fixed(25))),
+            (Role::DateKey, Style::new().bold().fg(Color::Fixed(28))),
+            (Role::AuthorKey, Style::new().bold().fg(Color::Fixed(124))),
+            (Role::SubjectKey, Style::new().bold().fg(Color::Fixed(130))),
+            (Role::Email, Style::new().fg(Color::Fixed(90))),
+            (Role::Date, Style::new().fg(Color::Fixed(28))),
+            (Role::PatchPrefix, Style::new().fg(Color::Fixed(130))),
+            (Role::PatchVersion, Style::new().fg(Color::Fixed(130))),
+            (Role::PatchIndex, Style::new().fg(Color::Fixed(124))),
+            (Role::Tags, Style::new().fg(Color::Fixed(28))),
+            (Role::Separator, Style::new().fg(Color::Fixed(244))),
+            (Role::Trailer, Style::new().fg(Color::Fixed(30))),
+            (Role::SignOff, Style::new().bold().fg(Color::Fixed(28))),
+            (Role::Review, Style::new().bold().fg(Color::Fixed(25))),
+            (Role::DiffHeader, Style::new().bold()),
+            (Role::HunkHeader, Style::new().fg(Color::Fixed(30))),
+            (Role::LineNumber, Style::new().fg(Color::Fixed(244))),
+            (Role::Added, Style::new().fg(Color::Fixed(28))),
+            (Role::Removed, Style::new().fg(Color::Fixed(124))),
+            (
+                Role::AddedWord,
+                Style::new().fg(Color::Fixed(22)).bg(Color::Fixed(194)),
+            ),
+            (
+                Role::RemovedWord,
+                Style::new().fg(Color::Fixed(88)).bg(Color::Fixed(224)),
+            ),
+            (Role::Whitespace, Style::new().bg(Color::Fixed(210))),
+            (Role::Signature, Style::new().fg(Color::Fixed(244))),
+            (Role::Keyword, Style::new().fg(Color::Fixed(130))),
+            (Role::Type, Style::new().fg(Color::Fixed(30))),
+            (Role::String, Style::new().fg(Color::Fixed(90))),
+            (Role::Number, Style::new().fg(Color::Fixed(90))),
+            (Role::Comment, Style::new().italic().fg(Color::Fixed(244))),
+            (Role::Meta, Style::new().fg(Color::Fixed(25))),
+        ])
+    }
+
+    /// No colors at all, only text attributes
+    pub fn monochrome() -> Theme {
+        Theme::from([
+            (Role::Source, Style::new().bold().underline()),
+            (Role::HeaderKey, Style::new().bold()),
+            (Role::FromKey, Style::new().bold()),
+            (Role::DateKey, Style::new().bold()),
+            (Role::AuthorKey, Style::new().bold()),
+            (Role::SubjectKey, Style::new().bold()),
+            (Role::Email, Style::new().underline()),
+            (Role::PatchPrefix, Style::new().bold()),
+            (Role::PatchVersion, Style::new().bold()),
+            (Role::PatchIndex, Style::new().bold()),
+            (Role::Tags, Style::new().italic()),
+            (Role::Separator, Style::new().dim()),
+            (Role::Trailer, Style::new().bold()),
+            (Role::SignOff, Style::new().bold()),
+            (Role::Review, Style::new().bold()),
+            (Role::DiffHeader, Style::new().bold()),
+            (Role::HunkHeader, Style::new().italic()),
+            (Role::LineNumber, Style::new().dim()),
+            (Role::Added, Style::new().bold()),
+            (Role::Removed, Style::new().dim()),
+            (Role::AddedWord, Style::new().bold().underline()),
+            (Role::RemovedWord, Style::new().dim().underline()),
+            (Role::Whitespace, Style::new().underline()),
+            (Role::Signature, Style::new().dim()),
+            (Role::Keyword, Style::new().bold()),
+            (Role::Type, Style::new().italic()),
+            (Role::Comment, Style::new().dim().italic()),
+            (Role::Meta, Style::new().bold()),
+        ])
+    }
+
+    fn on_dark_background(mut self) -> Theme {
+        self.dark = true;
+        self
+    }
+
+    pub fn is_dark(&self) -> bool {
+        self.dark
+    }
+
+    pub fn style(&self, role: Role) -> Style {
+        self.styles.get(&role).copied().unwrap_or_default()
+    }
+
+    pub fn set(&mut self, role: Role, style: Style) {
+        self.styles.insert(role, style);
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::dark()
+    }
+}
+
+impl<const N: usize> From<[(Role, Style); N]> for Theme {
+    fn from(styles: [(Role, Style); N]) -> Self {
+        Theme {
+            styles: HashMap::from(styles),
+            dark: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_themes() {
+        for name in Theme::BUILTIN {
+            assert!(Theme::builtin(name).is_some());
+        }
+
+        assert!(Theme::builtin("solarized").is_none());
+        assert!(Theme::monochrome()
+            .styles
+            .values()
+            .all(|style| style.fg.is_none() && style.bg.is_none()));
+    }
+
+    #[test]
+    fn override_style() {
+        let mut theme = Theme::default();
+        assert_eq!(theme.style(Role::Text), Style::new());
+
+        theme.set(Role::Email, Style::new().fg(Color::Rgb(255, 135, 0)));
+        assert_eq!(
+            theme.style(Role::Email),
+            Style::new().fg(Color::Rgb(255, 135, 0))
+        );
+    }
+}
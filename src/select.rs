@@ -0,0 +1,457 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use color_eyre::eyre::{bail, eyre};
+use papr::{
+    mailbox::{
+        message::{
+            header::{Person, Subject},
+            Message,
+        },
+        Mailbox,
+    },
+    query::Query,
+    utils,
+};
+use regex::Regex;
+
+/// Options picking some of the messages of each mailbox. Messages are numbered from 1 in the
+/// order they appear in their mailbox. Repeating an option picks the messages matching any of
+/// its values, different options must all match
+#[derive(Debug, Default, Clone, clap::Args)]
+#[command(next_help_heading = "Selection")]
+pub struct Selection {
+    /// Only the message with this number
+    #[clap(short, long, value_name = "N", value_parser = message_number)]
+    pub message: Vec<usize>,
+    /// Only the messages in this range of numbers, both ends included: `2..5`, `3..` or `..4`
+    #[clap(long, value_name = "A..B")]
+    pub range: Vec<Range>,
+    /// Only the message with this `Message-Id`, the `< >` are optional
+    #[clap(long, value_name = "MESSAGE-ID")]
+    pub id: Vec<String>,
+    /// Only the messages whose subject matches this regular expression. The `Subject` header is
+    /// matched as it is written, like `[PATCH v2 01/12] foo`
+    #[clap(long, value_name = "REGEX", value_parser = Regex::new)]
+    pub subject_match: Option<Regex>,
+    /// Only the patch with this index, like `4/10`, or `4` for the fourth patch of any series
+    #[clap(long, value_name = "I/N")]
+    pub patch: Vec<PatchNumber>,
+    #[clap(flatten)]
+    pub filters: Filters,
+}
+
+/// Options picking messages by their content. People are matched by address
+/// (`alice@example.com`), by domain (`@example.com`, with its subdomains) or by a part of their
+/// name or address
+#[derive(Debug, Default, Clone, clap::Args)]
+#[command(next_help_heading = "Filters")]
+pub struct Filters {
+    /// Only the messages from this person
+    #[clap(long, value_name = "WHO")]
+    pub from: Vec<String>,
+    /// Only the messages sent to this person
+    #[clap(long, value_name = "WHO")]
+    pub to: Vec<String>,
+    /// Only the messages with this person in copy
+    #[clap(long, value_name = "WHO")]
+    pub cc: Vec<String>,
+    /// Only the messages sent on or after this date: `2024-06-03`, `2024-06-03 12:30`, RFC 3339,
+    /// RFC 2822, `2 weeks ago` or `yesterday`
+    #[clap(long, value_name = "DATE", value_parser = since)]
+    pub since: Option<DateTime<Utc>>,
+    /// Only the messages sent before the end of this date, `--until 2024-06-03` includes that day
+    #[clap(long, value_name = "DATE", value_parser = until)]
+    pub until: Option<DateTime<Utc>>,
+    /// Only the messages with this header: `Name` for any value, `Name=value` for this value or
+    /// `Name~=regex` for a value matching the regular expression. Names ignore case
+    #[clap(long, value_name = "FILTER")]
+    pub header: Vec<HeaderFilter>,
+    /// Only the messages with this trailer, like `Reviewed-by`
+    #[clap(long, value_name = "KEY")]
+    pub has_trailer: Vec<String>,
+    /// Only the messages matching this query, like
+    /// `from:alice and subject:"net:" and not has:Reviewed-by and date>2024-01-01`
+    #[clap(long, value_name = "QUERY")]
+    pub query: Option<Query>,
+}
+
+/// A filter on the value of a header
+#[derive(Debug, Clone)]
+pub struct HeaderFilter {
+    pub name: String,
+    pub value: HeaderValue,
+}
+
+#[derive(Debug, Clone)]
+pub enum HeaderValue {
+    Any,
+    Equals(String),
+    Matches(Regex),
+}
+
+/// An inclusive range of message numbers, any end can be left open
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Range {
+    pub start: Option<usize>,
+    pub end: Option<usize>,
+}
+
+/// The `i/N` of a patch subject, `N` is optional
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PatchNumber {
+    pub index: usize,
+    pub total: Option<usize>,
+}
+
+impl Selection {
+    /// The positions (from 0) of the messages of `mailbox` picked by the selection
+    pub fn indexes(&self, mailbox: &Mailbox) -> Vec<usize> {
+        mailbox
+            .messages
+            .iter()
+            .enumerate()
+            .filter(|(index, message)| self.matches(*index + 1, message))
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Drops the messages of `mailbox` not picked by the selection
+    pub fn retain(&self, mailbox: &mut Mailbox) {
+        let mut number = 0;
+
+        mailbox.messages.retain(|message| {
+            number += 1;
+            self.matches(number, message)
+        });
+    }
+
+    /// Whether the message numbered `number` (from 1) is picked
+    pub fn matches(&self, number: usize, message: &Message) -> bool {
+        any(&self.message, |n| *n == number)
+            && any(&self.range, |range| range.contains(number))
+            && any(&self.id, |id| {
+                message.message_id() == Some(id.trim().trim_matches(|c| c == '<' || c == '>'))
+            })
+            && any(&self.patch, |patch| patch.matches(message))
+            && self.subject_match.as_ref().is_none_or(|regex| {
+                message
+                    .raw_header("Subject")
+                    .is_some_and(|subject| regex.is_match(subject))
+            })
+            && self.filters.matches(message)
+    }
+}
+
+impl Filters {
+    /// Whether the message passes every filter
+    pub fn matches(&self, message: &Message) -> bool {
+        let people = |people: &[Person], pattern: &String| {
+            people.iter().any(|person| person.matches(pattern))
+        };
+
+        any(&self.from, |pattern| {
+            message.from().is_some_and(|from| from.matches(pattern))
+        }) && any(&self.to, |pattern| people(message.to(), pattern))
+            && any(&self.cc, |pattern| people(message.cc(), pattern))
+            && self
+                .since
+                .is_none_or(|since| message.date().is_some_and(|date| since <= date))
+            && self
+                .until
+                .is_none_or(|until| message.date().is_some_and(|date| date < until))
+            && self.header.iter().all(|filter| filter.matches(message))
+            && self.has_trailer.iter().all(|key| {
+                message
+                    .body
+                    .trailers()
+                    .iter()
+                    .any(|(trailer, _)| trailer.eq_ignore_ascii_case(key.trim()))
+            })
+            && self
+                .query
+                .as_ref()
+                .is_none_or(|query| query.matches(message))
+    }
+}
+
+fn since(value: &str) -> color_eyre::Result<DateTime<Utc>> {
+    utils::parse_date(value, Utc::now()).map(|(start, _)| start)
+}
+
+fn until(value: &str) -> color_eyre::Result<DateTime<Utc>> {
+    utils::parse_date(value, Utc::now()).map(|(_, end)| end)
+}
+
+fn message_number(value: &str) -> color_eyre::Result<usize> {
+    match value.trim().parse() {
+        Ok(0) => bail!("Invalid message number `0`, messages are numbered from 1"),
+        Ok(number) => Ok(number),
+        Err(_) => bail!("Invalid message number `{}`", value),
+    }
+}
+
+impl HeaderFilter {
+    pub fn matches(&self, message: &Message) -> bool {
+        message
+            .headers
+            .iter()
+            .filter(|header| header.name().eq_ignore_ascii_case(&self.name))
+            .any(|header| match &self.value {
+                HeaderValue::Any => true,
+                HeaderValue::Equals(value) => header.value().trim() == value,
+                HeaderValue::Matches(regex) => regex.is_match(&header.value()),
+            })
+    }
+}
+
+impl FromStr for HeaderFilter {
+    type Err = color_eyre::Report;
+
+    fn from_str(filter: &str) -> Result<Self, Self::Err> {
+        let (name, value) = match filter.split_once('=') {
+            Some((name, regex)) if name.ends_with('~') => (
+                &name[..name.len() - 1],
+                HeaderValue::Matches(
+                    Regex::new(regex)
+                        .map_err(|e| eyre!("Invalid regular expression `{}`: {}", regex, e))?,
+                ),
+            ),
+            Some((name, value)) => (name, HeaderValue::Equals(value.trim().to_string())),
+            None => (filter, HeaderValue::Any),
+        };
+
+        let name = name.trim();
+
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            bail!(
+                "Invalid header filter `{}`, expected `Name`, `Name=value` or `Name~=regex`",
+                filter
+            );
+        }
+
+        Ok(HeaderFilter {
+            name: name.to_string(),
+            value,
+        })
+    }
+}
+
+/// Whether any of `values` matches, an option given no values matches everything
+fn any<T>(values: &[T], matches: impl Fn(&T) -> bool) -> bool {
+    values.is_empty() || values.iter().any(matches)
+}
+
+impl Range {
+    pub fn contains(&self, number: usize) -> bool {
+        self.start.is_none_or(|start| start <= number) && self.end.is_none_or(|end| number <= end)
+    }
+}
+
+impl FromStr for Range {
+    type Err = color_eyre::Report;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let Some((start, end)) = value.split_once("..") else {
+            bail!("Invalid range `{}`, expected `A..B`, `A..` or `..B`", value);
+        };
+
+        let number = |n: &str| -> Result<Option<usize>, Self::Err> {
+            match n.trim() {
+                "" => Ok(None),
+                n => match n.parse() {
+                    Ok(0) => bail!("Invalid range `{}`, messages are numbered from 1", value),
+                    Ok(n) => Ok(Some(n)),
+                    Err(_) => bail!("Invalid message number `{}` in range `{}`", n, value),
+                },
+            }
+        };
+
+        let range = Range {
+            start: number(start)?,
+            end: number(end)?,
+        };
+
+        match (range.start, range.end) {
+            (Some(start), Some(end)) if start > end => {
+                bail!("Invalid range `{}`, its start is after its end", value)
+            }
+            _ => Ok(range),
+        }
+    }
+}
+
+impl PatchNumber {
+    pub fn matches(&self, message: &Message) -> bool {
+        match message.subject() {
+            Some(Subject::Patch {
+                index: Some((index, total)),
+                ..
+            }) => *index == self.index && self.total.is_none_or(|t| t == *total),
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for PatchNumber {
+    type Err = color_eyre::Report;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || eyre!("Invalid patch `{}`, expected `I/N` or `I`", value);
+        let (index, total) = match value.split_once('/') {
+            Some((index, total)) => (index, Some(total)),
+            None => (value, None),
+        };
+
+        Ok(PatchNumber {
+            index: index.trim().parse().map_err(|_| invalid())?,
+            total: total
+                .map(|total| total.trim().parse())
+                .transpose()
+                .map_err(|_| invalid())?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_selectors() {
+        let range = |start, end| Range { start, end };
+
+        assert_eq!("2..5".parse::<Range>().unwrap(), range(Some(2), Some(5)));
+        assert_eq!("3..".parse::<Range>().unwrap(), range(Some(3), None));
+        assert_eq!("..4".parse::<Range>().unwrap(), range(None, Some(4)));
+        assert!("2-5".parse::<Range>().is_err());
+        assert!("a..5".parse::<Range>().is_err());
+        assert!("0..5".parse::<Range>().is_err());
+        assert!("..00".parse::<Range>().is_err());
+        assert!("4..2".parse::<Range>().is_err());
+        assert_eq!("2..2".parse::<Range>().unwrap(), range(Some(2), Some(2)));
+
+        assert_eq!(message_number("3").unwrap(), 3);
+        assert!(message_number("0").is_err());
+        assert!(message_number("a").is_err());
+
+        assert_eq!(
+            "4/10".parse::<PatchNumber>().unwrap(),
+            PatchNumber {
+                index: 4,
+                total: Some(10)
+            }
+        );
+        assert_eq!("4".parse::<PatchNumber>().unwrap().total, None);
+        assert!("4/".parse::<PatchNumber>().is_err());
+    }
+
+    #[test]
+    fn select_messages() {
+        let input = include_str!("mailbox/samples/thread.mbx");
+        let mailbox = Mailbox::try_from(input).unwrap();
+
+        let selection = Selection {
+            range: vec!["2..4".parse().unwrap()],
+            ..Default::default()
+        };
+        assert_eq!(selection.indexes(&mailbox), [1, 2, 3]);
+
+        let selection = Selection {
+            range: vec!["2..4".parse().unwrap()],
+            subject_match: Some(Regex::new("^\\[PATCH").unwrap()),
+            ..Default::default()
+        };
+        assert_eq!(selection.indexes(&mailbox), [1, 2]);
+
+        let selection = Selection {
+            message: vec![1, 5],
+            id: vec!["<20240605-baz@corp.example.org>".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(selection.indexes(&mailbox), [4]);
+
+        let selection = Selection {
+            patch: vec!["2/2".parse().unwrap()],
+            ..Default::default()
+        };
+        assert_eq!(selection.indexes(&mailbox), [2]);
+
+        let mut mailbox = mailbox;
+        Selection::default().retain(&mut mailbox);
+        assert_eq!(mailbox.messages.len(), 5);
+    }
+
+    #[test]
+    fn filter_messages() {
+        let input = include_str!("mailbox/samples/thread.mbx");
+        let mailbox = Mailbox::try_from(input).unwrap();
+        let indexes = |filters: Filters| {
+            Selection {
+                filters,
+                ..Default::default()
+            }
+            .indexes(&mailbox)
+        };
+
+        let filters = Filters {
+            from: vec!["@kernel.org".to_string(), "carol".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(indexes(filters), [3, 4]);
+
+        let filters = Filters {
+            from: vec!["example.org".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(indexes(filters), [4]);
+
+        let filters = Filters {
+            to: vec!["netdev@vger.kernel.org".to_string()],
+            cc: vec!["Bob".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(indexes(filters), [0, 1, 2]);
+
+        let filters = Filters {
+            since: Some(since("2024-06-04").unwrap()),
+            until: Some(until("2024-06-04").unwrap()),
+            ..Default::default()
+        };
+        assert_eq!(indexes(filters), [3]);
+
+        let filters = Filters {
+            header: vec!["list-id~=netdev".parse().unwrap()],
+            has_trailer: vec!["signed-off-by".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(indexes(filters), [1, 2]);
+
+        let filters = Filters {
+            header: vec!["In-Reply-To".parse().unwrap()],
+            ..Default::default()
+        };
+        assert_eq!(indexes(filters), [1, 2, 3]);
+
+        let filters = Filters {
+            from: vec!["alice".to_string()],
+            query: Some("is:cover or subject:bar".parse().unwrap()),
+            ..Default::default()
+        };
+        assert_eq!(indexes(filters), [0, 2]);
+    }
+
+    #[test]
+    fn parse_header_filters() {
+        let filter = "List-Id~=net.*".parse::<HeaderFilter>().unwrap();
+        assert_eq!(filter.name, "List-Id");
+        assert!(matches!(filter.value, HeaderValue::Matches(_)));
+
+        let filter = "X-Mailer = git".parse::<HeaderFilter>().unwrap();
+        assert_eq!(filter.name, "X-Mailer");
+        assert!(matches!(filter.value, HeaderValue::Equals(ref v) if v == "git"));
+
+        assert!("List-Id~=(".parse::<HeaderFilter>().is_err());
+        assert!("=value".parse::<HeaderFilter>().is_err());
+    }
+}
@@ -0,0 +1,605 @@
+//! Syntax highlighting of the code in diffs. The grammars are small lexers bundled with papr,
+//! they find the keywords, types, strings, numbers, comments and preprocessor-like constructs of
+//! a line, carrying the comments and strings left open from one line to the next
+
+use std::ops::Range;
+
+/// The languages papr can highlight
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Language {
+    C,
+    Rust,
+    Python,
+    Shell,
+    Makefile,
+    Devicetree,
+}
+
+/// What a piece of code is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Token {
+    Keyword,
+    Type,
+    String,
+    Number,
+    Comment,
+    /// Preprocessor directives, attributes, macros, decorators and variable expansions
+    Meta,
+}
+
+/// Where a line starts: in code, or in a comment or string left open by the line before it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum State {
+    #[default]
+    Code,
+    Comment,
+    /// In a string closed by this delimiter
+    String(&'static str),
+}
+
+/// How the tokens of a language are found
+struct Grammar {
+    keywords: &'static [&'static str],
+    types: &'static [&'static str],
+    /// Whether a word is a type even though it isn't among `types`
+    is_type: fn(&str) -> bool,
+    line_comment: Option<&'static str>,
+    /// Whether line comments only start at the start of a word, like in shell
+    comment_after_space: bool,
+    block_comment: Option<(&'static str, &'static str)>,
+    /// The delimiters of strings, longer ones first, and whether the strings can span lines
+    strings: &'static [(&'static str, bool)],
+    /// The end of the meta construct starting at the given position of the line, if any
+    meta: fn(&str, usize) -> Option<usize>,
+    /// Whether `name!` is a macro call, like in Rust
+    macros: bool,
+}
+
+impl Language {
+    /// The language of a file from its name or its extension
+    pub fn from_path(path: &str) -> Option<Language> {
+        let name = path.rsplit('/').next().unwrap_or(path);
+
+        match name {
+            "Makefile" | "makefile" | "GNUmakefile" | "Kbuild" => return Some(Language::Makefile),
+            _ => {}
+        }
+
+        match name.rsplit_once('.')?.1 {
+            "c" | "h" => Some(Language::C),
+            "rs" => Some(Language::Rust),
+            "py" => Some(Language::Python),
+            "sh" | "bash" => Some(Language::Shell),
+            "mk" => Some(Language::Makefile),
+            "dts" | "dtsi" | "dtso" => Some(Language::Devicetree),
+            _ => None,
+        }
+    }
+
+    fn grammar(self) -> &'static Grammar {
+        match self {
+            Language::C => &C,
+            Language::Rust => &RUST,
+            Language::Python => &PYTHON,
+            Language::Shell => &SHELL,
+            Language::Makefile => &MAKEFILE,
+            Language::Devicetree => &DEVICETREE,
+        }
+    }
+}
+
+const C: Grammar = Grammar {
+    keywords: &[
+        "asm", "auto", "break", "case", "const", "continue", "default", "do", "else", "enum",
+        "extern", "false", "for", "goto", "if", "inline", "NULL", "register", "restrict", "return",
+        "sizeof", "static", "struct", "switch", "true", "typedef", "typeof", "union", "volatile",
+        "while",
+    ],
+    types: &[
+        "bool", "char", "double", "float", "int", "long", "short", "signed", "unsigned", "void",
+        "_Bool", "u8", "u16", "u32", "u64", "s8", "s16", "s32", "s64", "__u8", "__u16", "__u32",
+        "__u64", "__s8", "__s16", "__s32", "__s64", "__le16", "__le32", "__le64", "__be16",
+        "__be32", "__be64",
+    ],
+    // Like `size_t` and `uint32_t`
+    is_type: |word| word.ends_with("_t"),
+    line_comment: Some("//"),
+    comment_after_space: false,
+    block_comment: Some(("/*", "*/")),
+    strings: &[("\"", false), ("'", false)],
+    meta: directive,
+    macros: false,
+};
+
+const RUST: Grammar = Grammar {
+    keywords: &[
+        "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+        "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move",
+        "mut", "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait",
+        "true", "type", "unsafe", "use", "where", "while",
+    ],
+    types: &[
+        "bool", "char", "str", "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32",
+        "i64", "i128", "isize", "f32", "f64",
+    ],
+    // Types, traits and variants are in `CamelCase`, constants are all uppercase
+    is_type: |word| {
+        word.starts_with(|c: char| c.is_ascii_uppercase()) && word.contains(char::is_lowercase)
+    },
+    line_comment: Some("//"),
+    comment_after_space: false,
+    block_comment: Some(("/*", "*/")),
+    strings: &[("\"", false)],
+    meta: |line, at| {
+        let rest = &line[at..];
+
+        if rest.starts_with("#[") || rest.starts_with("#![") {
+            return Some(at + closing(rest, '[', ']'));
+        }
+
+        // Unlike lifetimes, characters are closed right away like `'a'` or `'\n'`
+        let quoted = rest.strip_prefix('\'')?;
+        let escaped = quoted.starts_with('\\');
+        let end = quoted.char_indices().skip(1).find(|(_, c)| *c == '\'')?.0;
+
+        (end == quoted.chars().next()?.len_utf8() || escaped).then_some(at + end + 2)
+    },
+    macros: true,
+};
+
+const PYTHON: Grammar = Grammar {
+    keywords: &[
+        "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class",
+        "continue", "def", "del", "elif", "else", "except", "finally", "for", "from", "global",
+        "if", "import", "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return",
+        "try", "while", "with", "yield",
+    ],
+    types: &[
+        "bool", "bytes", "dict", "float", "int", "list", "object", "set", "str", "tuple", "type",
+    ],
+    is_type: |_| false,
+    line_comment: Some("#"),
+    comment_after_space: false,
+    block_comment: None,
+    strings: &[("\"\"\"", true), ("'''", true), ("\"", false), ("'", false)],
+    meta: |line, at| {
+        let decorator = line[at..].strip_prefix('@')?;
+
+        line[..at]
+            .trim()
+            .is_empty()
+            .then(|| at + 1 + word_end(decorator, |c| c.is_alphanumeric() || c == '_' || c == '.'))
+    },
+    macros: false,
+};
+
+const SHELL: Grammar = Grammar {
+    keywords: &[
+        "break", "case", "continue", "declare", "do", "done", "elif", "else", "esac", "exit",
+        "export", "fi", "for", "function", "if", "in", "local", "readonly", "return", "select",
+        "shift", "then", "time", "unset", "until", "while",
+    ],
+    types: &[],
+    is_type: |_| false,
+    line_comment: Some("#"),
+    comment_after_space: true,
+    block_comment: None,
+    strings: &[("\"", false), ("'", false)],
+    meta: expansion,
+    macros: false,
+};
+
+const MAKEFILE: Grammar = Grammar {
+    keywords: &[
+        "define", "else", "endef", "endif", "export", "ifdef", "ifeq", "ifndef", "ifneq",
+        "include", "override", "sinclude", "unexport", "vpath",
+    ],
+    types: &[],
+    is_type: |_| false,
+    line_comment: Some("#"),
+    comment_after_space: false,
+    block_comment: None,
+    strings: &[],
+    meta: expansion,
+    macros: false,
+};
+
+const DEVICETREE: Grammar = Grammar {
+    keywords: &[],
+    types: &[],
+    is_type: |_| false,
+    line_comment: Some("//"),
+    comment_after_space: false,
+    block_comment: Some(("/*", "*/")),
+    strings: &[("\"", false)],
+    meta: |line, at| {
+        let rest = &line[at..];
+
+        // Directives like `/dts-v1/` and `/delete-node/`, and references to labels like `&uart0`
+        if let Some(name) = rest.strip_prefix('/') {
+            let end = word_end(name, |c| {
+                c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-'
+            });
+            return (end > 0 && name[end..].starts_with('/')).then_some(at + end + 2);
+        }
+
+        if let Some(label) = rest.strip_prefix('&') {
+            let end = word_end(label, |c| c.is_alphanumeric() || c == '_');
+            return (end > 0).then_some(at + 1 + end);
+        }
+
+        directive(line, at)
+    },
+    macros: false,
+};
+
+/// A C preprocessor directive like `#include <foo.h>` or `# define`, only at the start of a line.
+/// The path of an include is part of it
+fn directive(line: &str, at: usize) -> Option<usize> {
+    let rest = line[at..].strip_prefix('#')?;
+
+    if !line[..at].trim().is_empty() {
+        return None;
+    }
+
+    let name = rest.trim_start();
+    let end = word_end(name, |c| c.is_ascii_alphabetic());
+
+    match &name[..end] {
+        "" => None,
+        "include" => Some(line.len()),
+        _ => Some(line.len() - name.len() + end),
+    }
+}
+
+/// A shell or make expansion like `$foo`, `${foo}`, `$(CC)` or `$@`
+fn expansion(line: &str, at: usize) -> Option<usize> {
+    let rest = line[at..].strip_prefix('$')?;
+
+    match rest.chars().next()? {
+        '(' => Some(at + 1 + closing(rest, '(', ')')),
+        '{' => Some(at + 1 + closing(rest, '{', '}')),
+        c if c.is_alphabetic() || c == '_' => {
+            Some(at + 1 + word_end(rest, |c| c.is_alphanumeric() || c == '_'))
+        }
+        c if "@<^+?*#$!0123456789-".contains(c) => Some(at + 1 + c.len_utf8()),
+        _ => None,
+    }
+}
+
+/// The end of the text opened by the `open` starting `text`, nested pairs included, or the end of
+/// the text when it isn't closed
+fn closing(text: &str, open: char, close: char) -> usize {
+    let mut depth = 0;
+
+    for (at, c) in text.char_indices() {
+        if c == open {
+            depth += 1;
+        } else if c == close {
+            depth -= 1;
+
+            if depth == 0 {
+                return at + 1;
+            }
+        }
+    }
+
+    text.len()
+}
+
+/// The length of the start of `text` made of characters accepted by `accept`
+fn word_end(text: &str, accept: impl Fn(char) -> bool) -> usize {
+    text.find(|c| !accept(c)).unwrap_or(text.len())
+}
+
+/// Where the string or comment starting at `from` is closed by `close`, just after it, skipping
+/// escaped characters
+fn find_close(line: &str, from: usize, close: &str) -> Option<usize> {
+    let mut at = from;
+
+    while at < line.len() {
+        let rest = &line[at..];
+
+        if rest.starts_with(close) {
+            return Some(at + close.len());
+        }
+
+        let skip = if rest.starts_with('\\') { 2 } else { 1 };
+        at += rest.chars().take(skip).map(char::len_utf8).sum::<usize>();
+    }
+
+    None
+}
+
+/// The tokens of a line of code in `language`, in order. `state` is where the line starts and
+/// becomes where the next line starts
+pub fn highlight(language: Language, line: &str, state: &mut State) -> Vec<(Range<usize>, Token)> {
+    let grammar = language.grammar();
+    let mut tokens = Vec::new();
+    let mut at = 0;
+
+    match *state {
+        State::Code => {}
+        State::Comment => {
+            let (_, close) = grammar.block_comment.unwrap_or(("", ""));
+            let Some(end) = line.find(close).map(|end| end + close.len()) else {
+                tokens.push((0..line.len(), Token::Comment));
+                return tokens;
+            };
+
+            tokens.push((0..end, Token::Comment));
+            at = end;
+            *state = State::Code;
+        }
+        State::String(close) => {
+            let Some(end) = find_close(line, 0, close) else {
+                tokens.push((0..line.len(), Token::String));
+                return tokens;
+            };
+
+            tokens.push((0..end, Token::String));
+            at = end;
+            *state = State::Code;
+        }
+    }
+
+    while at < line.len() {
+        let rest = &line[at..];
+        let Some(c) = rest.chars().next() else {
+            break;
+        };
+
+        let comment = grammar
+            .line_comment
+            .is_some_and(|comment| rest.starts_with(comment));
+
+        if comment
+            && (!grammar.comment_after_space
+                || at == 0
+                || line[..at].ends_with(char::is_whitespace))
+        {
+            tokens.push((at..line.len(), Token::Comment));
+            break;
+        }
+
+        if let Some((open, close)) = grammar
+            .block_comment
+            .filter(|(open, _)| rest.starts_with(open))
+        {
+            match line[at + open.len()..].find(close) {
+                Some(end) => {
+                    let end = at + open.len() + end + close.len();
+                    tokens.push((at..end, Token::Comment));
+                    at = end;
+                    continue;
+                }
+                None => {
+                    tokens.push((at..line.len(), Token::Comment));
+                    *state = State::Comment;
+                    break;
+                }
+            }
+        }
+
+        if let Some((delimiter, multiline)) = grammar
+            .strings
+            .iter()
+            .find(|(delimiter, _)| rest.starts_with(delimiter))
+        {
+            match find_close(line, at + delimiter.len(), delimiter) {
+                Some(end) => {
+                    tokens.push((at..end, Token::String));
+                    at = end;
+                    continue;
+                }
+                None => {
+                    tokens.push((at..line.len(), Token::String));
+
+                    if *multiline {
+                        *state = State::String(delimiter);
+                    }
+
+                    break;
+                }
+            }
+        }
+
+        if let Some(end) = (grammar.meta)(line, at) {
+            tokens.push((at..end, Token::Meta));
+            at = end;
+            continue;
+        }
+
+        if c.is_ascii_digit() {
+            let end = at + word_end(rest, |c| c.is_ascii_alphanumeric() || c == '_' || c == '.');
+            tokens.push((at..end, Token::Number));
+            at = end;
+            continue;
+        }
+
+        if c.is_alphabetic() || c == '_' {
+            let end = at + word_end(rest, |c| c.is_alphanumeric() || c == '_');
+            let word = &line[at..end];
+
+            if grammar.macros && line[end..].starts_with('!') && !line[end..].starts_with("!=") {
+                tokens.push((at..end + 1, Token::Meta));
+                at = end + 1;
+                continue;
+            }
+
+            if grammar.keywords.contains(&word) {
+                tokens.push((at..end, Token::Keyword));
+            } else if grammar.types.contains(&word) || (grammar.is_type)(word) {
+                tokens.push((at..end, Token::Type));
+            }
+
+            at = end;
+            continue;
+        }
+
+        at += c.len_utf8();
+    }
+
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The text of each token of the lines, with the state carried between them
+    fn highlighted(language: Language, lines: &[&str]) -> Vec<(String, Token)> {
+        let mut state = State::default();
+
+        lines
+            .iter()
+            .flat_map(|line| {
+                highlight(language, line, &mut state)
+                    .into_iter()
+                    .map(|(range, token)| (line[range].to_string(), token))
+            })
+            .collect()
+    }
+
+    fn texts(tokens: &[(String, Token)], token: Token) -> Vec<&str> {
+        tokens
+            .iter()
+            .filter(|(_, kind)| *kind == token)
+            .map(|(text, _)| text.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn detect_languages() {
+        assert_eq!(Language::from_path("net/core/foo.c"), Some(Language::C));
+        assert_eq!(Language::from_path("include/foo.h"), Some(Language::C));
+        assert_eq!(
+            Language::from_path("rust/kernel/lib.rs"),
+            Some(Language::Rust)
+        );
+        assert_eq!(
+            Language::from_path("scripts/checkpatch.py"),
+            Some(Language::Python)
+        );
+        assert_eq!(Language::from_path("tools/run.sh"), Some(Language::Shell));
+        assert_eq!(
+            Language::from_path("drivers/Makefile"),
+            Some(Language::Makefile)
+        );
+        assert_eq!(
+            Language::from_path("arch/arm64/boot/dts/foo.dtsi"),
+            Some(Language::Devicetree)
+        );
+        assert_eq!(Language::from_path("Documentation/foo.rst"), None);
+        assert_eq!(Language::from_path("README"), None);
+    }
+
+    #[test]
+    fn highlight_c() {
+        let tokens = highlighted(
+            Language::C,
+            &[
+                "#include <linux/foo.h>",
+                "static int foo(size_t len) /* the",
+                " * length */ { return len > 0x10 ? -EINVAL : 'a'; } // done",
+            ],
+        );
+
+        assert_eq!(texts(&tokens, Token::Meta), ["#include <linux/foo.h>"]);
+        assert_eq!(texts(&tokens, Token::Keyword), ["static", "return"]);
+        assert_eq!(texts(&tokens, Token::Type), ["int", "size_t"]);
+        assert_eq!(
+            texts(&tokens, Token::Comment),
+            ["/* the", " * length */", "// done"]
+        );
+        assert_eq!(texts(&tokens, Token::Number), ["0x10"]);
+        assert_eq!(texts(&tokens, Token::String), ["'a'"]);
+    }
+
+    #[test]
+    fn highlight_rust() {
+        let tokens = highlighted(
+            Language::Rust,
+            &["#[derive(Debug)] fn foo<'a>(x: &'a str) -> Option<u8> { println!(\"{}\", '\\n'); }"],
+        );
+
+        assert_eq!(
+            texts(&tokens, Token::Meta),
+            ["#[derive(Debug)]", "println!", "'\\n'"]
+        );
+        assert_eq!(texts(&tokens, Token::Keyword), ["fn"]);
+        assert_eq!(texts(&tokens, Token::Type), ["str", "Option", "u8"]);
+        assert_eq!(texts(&tokens, Token::String), ["\"{}\""]);
+    }
+
+    #[test]
+    fn highlight_python() {
+        let tokens = highlighted(
+            Language::Python,
+            &[
+                "@pytest.mark.slow",
+                "def foo(x: int) -> str:",
+                "    \"\"\"Foo",
+                "    bar\"\"\" # x",
+            ],
+        );
+
+        assert_eq!(texts(&tokens, Token::Meta), ["@pytest.mark.slow"]);
+        assert_eq!(texts(&tokens, Token::Keyword), ["def"]);
+        assert_eq!(texts(&tokens, Token::Type), ["int", "str"]);
+        assert_eq!(
+            texts(&tokens, Token::String),
+            ["\"\"\"Foo", "    bar\"\"\""]
+        );
+        assert_eq!(texts(&tokens, Token::Comment), ["# x"]);
+    }
+
+    #[test]
+    fn highlight_shell_and_make() {
+        let tokens = highlighted(
+            Language::Shell,
+            &["if [ -n \"$1\" ]; then echo ${#foo} $bar; fi # end"],
+        );
+
+        assert_eq!(texts(&tokens, Token::Keyword), ["if", "then", "fi"]);
+        assert_eq!(texts(&tokens, Token::Meta), ["${#foo}", "$bar"]);
+        assert_eq!(texts(&tokens, Token::String), ["\"$1\""]);
+        assert_eq!(texts(&tokens, Token::Comment), ["# end"]);
+
+        let tokens = highlighted(
+            Language::Makefile,
+            &[
+                "obj-$(CONFIG_FOO) += foo.o",
+                "ifdef CONFIG_BAR # bar",
+                "\t$(CC) -o $@ $<",
+            ],
+        );
+
+        assert_eq!(
+            texts(&tokens, Token::Meta),
+            ["$(CONFIG_FOO)", "$(CC)", "$@", "$<"]
+        );
+        assert_eq!(texts(&tokens, Token::Keyword), ["ifdef"]);
+        assert_eq!(texts(&tokens, Token::Comment), ["# bar"]);
+    }
+
+    #[test]
+    fn highlight_devicetree() {
+        let tokens = highlighted(
+            Language::Devicetree,
+            &[
+                "/dts-v1/;",
+                "#include <dt-bindings/gpio/gpio.h>",
+                "&uart0 { compatible = \"foo,bar\"; reg = <0x1000 0x100>; }; // uart",
+            ],
+        );
+
+        assert_eq!(
+            texts(&tokens, Token::Meta),
+            ["/dts-v1/", "#include <dt-bindings/gpio/gpio.h>", "&uart0"]
+        );
+        assert_eq!(texts(&tokens, Token::String), ["\"foo,bar\""]);
+        assert_eq!(texts(&tokens, Token::Number), ["0x1000", "0x100"]);
+        assert_eq!(texts(&tokens, Token::Comment), ["// uart"]);
+    }
+}
@@ -1,4 +1,5 @@
-use rayon::iter::{ParallelBridge, ParallelIterator};
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use color_eyre::eyre::{bail, eyre, Result};
 
 fn find_messages(input: &str) -> Vec<usize> {
     let mut input = input;
@@ -15,23 +16,128 @@ fn find_messages(input: &str) -> Vec<usize> {
     messages
 }
 
-pub fn capture_messages<'input>(input: &'input str) -> Vec<&'input str> {
+/// Splits a mbox file in the slices of each of its messages, keeping the order they appear in
+pub fn capture_messages(input: &str) -> Vec<&str> {
     let starts = find_messages(input);
-    let mut ends = starts.clone();
-    ends.push(input.len());
-    ends.remove(0);
+    let ends = starts.iter().skip(1).copied().chain([input.len()]);
 
-    let ranges = starts.iter().zip(ends.iter());
+    starts
+        .iter()
+        .zip(ends)
+        .map(|(start, end)| &input[*start..end])
+        .collect()
+}
+
+/// Parses a date written by a user into the span of time it covers, the end being excluded.
+/// Dates without a timezone are in UTC
+///
+/// - `2024-06-03` is the whole day, `today` and `yesterday` too
+/// - `2024-06-03 12:30`, `2024-06-03T12:30:15`, RFC 3339 and RFC 2822 dates are a single second
+/// - `3 days ago` or `3.days.ago` is the second that long before `now`, the units go from
+///   `seconds` to `years`, months are 30 days long and years 365
+pub fn parse_date(value: &str, now: DateTime<Utc>) -> Result<(DateTime<Utc>, DateTime<Utc>)> {
+    let value = value.trim();
+    let day = |date: NaiveDate| {
+        let start = Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0).unwrap());
+        (start, start + Duration::days(1))
+    };
+    let instant = |date: DateTime<Utc>| (date, date + Duration::seconds(1));
+
+    match value {
+        "now" => return Ok(instant(now)),
+        "today" => return Ok(day(now.date_naive())),
+        "yesterday" => return Ok(day(now.date_naive() - Duration::days(1))),
+        _ => {}
+    }
+
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(day(date));
+    }
+
+    for format in ["%Y-%m-%d %H:%M", "%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S"] {
+        if let Ok(date) = NaiveDateTime::parse_from_str(value, format) {
+            return Ok(instant(Utc.from_utc_datetime(&date)));
+        }
+    }
+
+    if let Ok(date) = DateTime::parse_from_rfc3339(value).or(DateTime::parse_from_rfc2822(value)) {
+        return Ok(instant(date.to_utc()));
+    }
 
-    ranges.par_bridge().map(|(start, end)| {
-        &input[*start..*end]
-    }).collect()
+    let words = value
+        .split(['.', ' '])
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<_>>();
+
+    if let [amount, unit, "ago"] = words[..] {
+        let amount = amount.parse::<i64>().ok();
+        let seconds = match unit.strip_suffix('s').unwrap_or(unit) {
+            "second" => Some(1),
+            "minute" => Some(60),
+            "hour" => Some(60 * 60),
+            "day" => Some(24 * 60 * 60),
+            "week" => Some(7 * 24 * 60 * 60),
+            "month" => Some(30 * 24 * 60 * 60),
+            "year" => Some(365 * 24 * 60 * 60),
+            _ => None,
+        };
+
+        if let (Some(amount), Some(seconds)) = (amount, seconds) {
+            let date = amount
+                .checked_mul(seconds)
+                .and_then(Duration::try_seconds)
+                .and_then(|ago| now.checked_sub_signed(ago))
+                .ok_or_else(|| eyre!("Invalid date `{}`, it is too far from now", value))?;
+
+            return Ok(instant(date));
+        }
+    }
+
+    bail!(
+        "Invalid date `{}`, expected `YYYY-MM-DD`, `YYYY-MM-DD HH:MM`, a RFC 3339 or RFC 2822 date, or `N days ago`",
+        value
+    )
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn parse_dates() {
+        let now = Utc.with_ymd_and_hms(2024, 6, 10, 15, 30, 0).unwrap();
+        let at = |y, m, d, h, min, s| Utc.with_ymd_and_hms(y, m, d, h, min, s).unwrap();
+
+        assert_eq!(
+            parse_date("2024-06-03", now).unwrap(),
+            (at(2024, 6, 3, 0, 0, 0), at(2024, 6, 4, 0, 0, 0))
+        );
+        assert_eq!(
+            parse_date("yesterday", now).unwrap(),
+            (at(2024, 6, 9, 0, 0, 0), at(2024, 6, 10, 0, 0, 0))
+        );
+        assert_eq!(
+            parse_date("2024-06-03 12:30", now).unwrap().0,
+            at(2024, 6, 3, 12, 30, 0)
+        );
+        assert_eq!(
+            parse_date("2024-06-03T12:30:00+02:00", now).unwrap().0,
+            at(2024, 6, 3, 10, 30, 0)
+        );
+        assert_eq!(
+            parse_date("1 week ago", now).unwrap().0,
+            at(2024, 6, 3, 15, 30, 0)
+        );
+        assert_eq!(
+            parse_date("2.days.ago", now).unwrap().0,
+            at(2024, 6, 8, 15, 30, 0)
+        );
+        assert!(parse_date("99999999 years ago", now).is_err());
+        assert!(parse_date("9223372036854775807.seconds.ago", now).is_err());
+        assert!(parse_date("last tuesday", now).is_err());
+        assert!(parse_date("2024-13-01", now).is_err());
+    }
+
     #[test]
     fn find_messages_test() {
         let input = include_str!("samples/multi_foo_messages.mbx");
@@ -0,0 +1,304 @@
+//! Whitespace errors in the lines added by patches, like `git diff --check` with the rules of
+//! git's `core.whitespace`
+
+use std::{ops::Range, str::FromStr};
+
+use color_eyre::eyre::eyre;
+
+use crate::diff::{Kind, Patch};
+
+/// A kind of whitespace error
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum Rule {
+    /// Spaces or tabs ending a line
+    BlankAtEol,
+    /// A space before a tab in the indentation of a line
+    SpaceBeforeTab,
+    /// The last line of a file isn't ended by a newline
+    MissingNewline,
+    /// A line ended by `\r\n` instead of `\n`
+    Crlf,
+}
+
+impl Rule {
+    pub const ALL: [Rule; 4] = [
+        Rule::BlankAtEol,
+        Rule::SpaceBeforeTab,
+        Rule::MissingNewline,
+        Rule::Crlf,
+    ];
+
+    /// The name of the rule in configuration files and on the command line
+    pub fn name(self) -> &'static str {
+        match self {
+            Rule::BlankAtEol => "blank-at-eol",
+            Rule::SpaceBeforeTab => "space-before-tab",
+            Rule::MissingNewline => "missing-newline",
+            Rule::Crlf => "crlf",
+        }
+    }
+
+    /// What the lines breaking the rule have, to describe them
+    pub fn description(self) -> &'static str {
+        match self {
+            Rule::BlankAtEol => "trailing whitespace",
+            Rule::SpaceBeforeTab => "a space before a tab in their indentation",
+            Rule::MissingNewline => "no newline at the end of the file",
+            Rule::Crlf => "a CRLF line ending",
+        }
+    }
+
+    fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+impl FromStr for Rule {
+    type Err = color_eyre::Report;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Rule::ALL
+            .into_iter()
+            .find(|rule| rule.name() == value.trim())
+            .ok_or_else(|| {
+                let names = Rule::ALL.map(Rule::name);
+                eyre!(
+                    "Unknown whitespace rule `{}`, expected one of {}",
+                    value,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+/// The rules to check, every rule by default
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rules(u8);
+
+impl Rules {
+    pub const NONE: Rules = Rules(0);
+
+    pub fn contains(self, rule: Rule) -> bool {
+        self.0 & rule.bit() != 0
+    }
+
+    pub fn insert(&mut self, rule: Rule) {
+        self.0 |= rule.bit();
+    }
+
+    pub fn remove(&mut self, rule: Rule) {
+        self.0 &= !rule.bit();
+    }
+
+    pub fn is_empty(self) -> bool {
+        self == Rules::NONE
+    }
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        let mut rules = Rules::NONE;
+
+        for rule in Rule::ALL {
+            rules.insert(rule);
+        }
+
+        rules
+    }
+}
+
+impl FromStr for Rules {
+    type Err = color_eyre::Report;
+
+    /// Parses a comma separated list like `core.whitespace`, starting from every rule: a name
+    /// enables its rule, a name prefixed with `-` disables it and `none` disables them all
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut rules = Rules::default();
+
+        for name in value
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+        {
+            match name.strip_prefix('-') {
+                _ if name == "none" => rules = Rules::NONE,
+                Some(name) => rules.remove(name.parse()?),
+                None => rules.insert(name.parse()?),
+            }
+        }
+
+        Ok(rules)
+    }
+}
+
+/// A whitespace error in a line, `range` is where it is in the line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    pub rule: Rule,
+    pub range: Range<usize>,
+}
+
+/// The whitespace errors of an added line, given without its `+` and its `\n`. The `\r` of a
+/// CRLF line ending is part of the line. A missing newline can't be found from the line alone,
+/// see [`count`]
+pub fn line_errors(line: &str, rules: Rules) -> Vec<Error> {
+    let mut errors = Vec::new();
+    let content = line.strip_suffix('\r').unwrap_or(line);
+    let indent = &content[..content.len() - content.trim_start_matches([' ', '\t']).len()];
+
+    if rules.contains(Rule::SpaceBeforeTab) {
+        if let Some(tab) = indent.rfind('\t') {
+            if let Some(space) = indent[..tab].find(' ') {
+                errors.push(Error {
+                    rule: Rule::SpaceBeforeTab,
+                    range: space..tab + 1,
+                });
+            }
+        }
+    }
+
+    let trimmed = content.trim_end_matches([' ', '\t']);
+
+    if rules.contains(Rule::BlankAtEol) && trimmed.len() < content.len() {
+        errors.push(Error {
+            rule: Rule::BlankAtEol,
+            range: trimmed.len()..content.len(),
+        });
+    }
+
+    if rules.contains(Rule::Crlf) && content.len() < line.len() {
+        errors.push(Error {
+            rule: Rule::Crlf,
+            range: content.len()..line.len(),
+        });
+    }
+
+    errors
+}
+
+/// How many lines added by `diff`, the part of a patch after its `---` line, break each rule,
+/// for the rules broken at least once
+pub fn count(diff: &str, rules: Rules) -> Vec<(Rule, usize)> {
+    let mut counts = [0; Rule::ALL.len()];
+    let patch = Patch::parse(diff);
+
+    for hunk in patch.files.iter().flat_map(|file| &file.hunks) {
+        for (index, line) in hunk.lines.iter().enumerate() {
+            match line.kind {
+                Kind::Added => {
+                    let mut errors = line_errors(&line.raw[line.columns..], rules)
+                        .into_iter()
+                        .map(|error| error.rule)
+                        .collect::<Vec<_>>();
+                    errors.dedup();
+
+                    for rule in errors {
+                        counts[rule as usize] += 1;
+                    }
+                }
+                Kind::NoNewline
+                    if rules.contains(Rule::MissingNewline)
+                        && index > 0
+                        && hunk.lines[index - 1].kind == Kind::Added =>
+                {
+                    counts[Rule::MissingNewline as usize] += 1;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    Rule::ALL
+        .into_iter()
+        .zip(counts)
+        .filter(|(_, count)| *count > 0)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFF: &str = "diff --git a/foo.c b/foo.c
+--- a/foo.c
++++ b/foo.c
+@@ -1,3 +1,6 @@
+ int foo;
++int bar; \t
++ \tint baz;\r
+-int qux;
++    \t
+ int end;
++int last;
+\\ No newline at end of file
+--
+2.43.0
+";
+
+    #[test]
+    fn find_line_errors() {
+        let rules = |line| {
+            line_errors(line, Rules::default())
+                .into_iter()
+                .map(|error| (error.rule, error.range))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(rules("int foo;"), []);
+        assert_eq!(rules("int foo;  "), [(Rule::BlankAtEol, 8..10)]);
+        assert_eq!(
+            rules("  \tfoo;\r"),
+            [(Rule::SpaceBeforeTab, 0..3), (Rule::Crlf, 7..8)]
+        );
+        assert_eq!(rules("\t  foo;"), []);
+        assert_eq!(
+            rules(" \t "),
+            [(Rule::SpaceBeforeTab, 0..2), (Rule::BlankAtEol, 0..3)]
+        );
+        assert_eq!(
+            line_errors("foo; \r", "-crlf".parse().unwrap()),
+            [Error {
+                rule: Rule::BlankAtEol,
+                range: 4..5
+            }]
+        );
+    }
+
+    #[test]
+    fn count_errors() {
+        assert_eq!(
+            count(DIFF, Rules::default()),
+            [
+                (Rule::BlankAtEol, 2),
+                (Rule::SpaceBeforeTab, 2),
+                (Rule::MissingNewline, 1),
+                (Rule::Crlf, 1),
+            ]
+        );
+        assert_eq!(count(DIFF, "none,crlf".parse().unwrap()), [(Rule::Crlf, 1)]);
+        assert_eq!(count(DIFF, Rules::NONE), []);
+
+        // Removed lines starting with `-` and added lines starting with `+` aren't signatures
+        let diff = "diff --git a/foo.c b/foo.c\n--- a/foo.c\n+++ b/foo.c\n@@ -1,2 +1,3 @@\n--\n+- \n++++ \n int end;\n-- \n2.43.0\n";
+        assert_eq!(count(diff, Rules::default()), [(Rule::BlankAtEol, 2)]);
+    }
+
+    #[test]
+    fn parse_rules() {
+        assert_eq!("".parse::<Rules>().unwrap(), Rules::default());
+        assert_eq!("none".parse::<Rules>().unwrap(), Rules::NONE);
+
+        let rules = "-crlf, -missing-newline".parse::<Rules>().unwrap();
+        assert!(rules.contains(Rule::BlankAtEol));
+        assert!(!rules.contains(Rule::Crlf));
+        assert!(!rules.contains(Rule::MissingNewline));
+
+        assert_eq!(
+            "tabs".parse::<Rules>().unwrap_err().to_string(),
+            "Unknown whitespace rule `tabs`, expected one of blank-at-eol, space-before-tab, \
+             missing-newline, crlf"
+        );
+    }
+}